@@ -1,10 +1,19 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use glowing_waffle::{
     animation::Animation,
+    clock::FixedStepClock,
+    framebuffer::{FrameBuffer, HeadlessTarget},
     renderer::{ColorMode, Renderer},
+    screen::ScreenBuffer,
     waffle::{Size, Waffle},
 };
 
+/// Terminal sizes rendered at, from a small window to a large fullscreen one
+const TERMINAL_SIZES: [(u16, u16); 3] = [(80, 24), (120, 40), (240, 70)];
+
+/// Number of consecutive frames the encoding benchmarks cycle through
+const ENCODED_FRAMES: usize = 60;
+
 fn waffle_creation_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("Waffle Creation");
     
@@ -54,7 +63,7 @@ fn waffle_rotation_benchmark(c: &mut Criterion) {
 fn animation_update_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("Animation Update");
     
-    let mut animation = Animation::new();
+    let mut animation = Animation::with_clock(FixedStepClock::from_fps(30));
     let mut small_waffle = Waffle::new(Size::Small);
     let mut medium_waffle = Waffle::new(Size::Medium);
     let mut large_waffle = Waffle::new(Size::Large);
@@ -80,13 +89,131 @@ fn animation_update_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
-// Note: Cannot benchmark rendering in an automated fashion since it requires terminal interaction
-// These benchmarks focus on the computation aspects only
+fn frame_rendering_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Frame Rendering");
+    
+    for (width, height) in TERMINAL_SIZES {
+        let mut target = HeadlessTarget::new(width, height);
+        let mut renderer = Renderer::new(ColorMode::Rainbow);
+        let mut animation = Animation::with_clock(FixedStepClock::from_fps(30));
+        let mut waffle = Waffle::new(Size::Medium);
+        
+        // In the bytes these frames are sent as, so the rate lines up with the ANSI encoding
+        // group and shows which of the two limits the output
+        group.throughput(Throughput::Bytes(average_bytes(&animated_frames(width, height), false)));
+        group.bench_function(BenchmarkId::from_parameter(format!("{}x{}", width, height)), |b| {
+            b.iter(|| {
+                animation.update(&mut waffle);
+                renderer.render(&mut target, &waffle, &animation.frame_context()).unwrap();
+            })
+        });
+    }
+    
+    group.finish();
+}
+
+fn color_mode_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Color Mode Rendering");
+    let (width, height) = TERMINAL_SIZES[1];
+    
+    for mode in ColorMode::ALL {
+        let mut target = HeadlessTarget::new(width, height);
+        let mut renderer = Renderer::new(mode);
+        let mut animation = Animation::with_clock(FixedStepClock::from_fps(30));
+        let mut waffle = Waffle::new(Size::Medium);
+        
+        group.bench_function(BenchmarkId::from_parameter(mode), |b| {
+            b.iter(|| {
+                animation.update(&mut waffle);
+                renderer.render(&mut target, &waffle, &animation.frame_context()).unwrap();
+            })
+        });
+    }
+    
+    group.finish();
+}
+
+/// Render a run of consecutive frames to encode
+fn animated_frames(width: u16, height: u16) -> Vec<FrameBuffer> {
+    let mut renderer = Renderer::new(ColorMode::Rainbow);
+    let mut animation = Animation::with_clock(FixedStepClock::from_fps(30));
+    let mut waffle = Waffle::new(Size::Medium);
+    
+    (0..ENCODED_FRAMES)
+        .map(|_| {
+            animation.update(&mut waffle);
+            let mut frame = FrameBuffer::new(width, height);
+            renderer.render_frame(&mut frame, &waffle, &animation.frame_context());
+            frame
+        })
+        .collect()
+}
+
+/// Get the average number of bytes emitted per frame when encoding the frames in order
+fn average_bytes(frames: &[FrameBuffer], full_redraw: bool) -> u64 {
+    let mut screen = ScreenBuffer::new();
+    let mut out = Vec::new();
+    screen.write_frame(&frames[frames.len() - 1], &mut out).unwrap();
+    
+    let mut total = 0;
+    for frame in frames {
+        if full_redraw {
+            screen.invalidate();
+        }
+        out.clear();
+        screen.write_frame(frame, &mut out).unwrap();
+        total += out.len() as u64;
+    }
+    total / frames.len() as u64
+}
+
+fn ansi_encoding_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ANSI Encoding");
+    
+    for (width, height) in TERMINAL_SIZES {
+        let frames = animated_frames(width, height);
+        let size = format!("{}x{}", width, height);
+        
+        // Only the cells that changed since the previous frame, as sent to the terminal every frame
+        group.throughput(Throughput::Bytes(average_bytes(&frames, false)));
+        group.bench_function(BenchmarkId::new("changed cells", &size), |b| {
+            let mut screen = ScreenBuffer::new();
+            let mut out = Vec::new();
+            let mut next = 0;
+            b.iter(|| {
+                out.clear();
+                screen.write_frame(&frames[next], &mut out).unwrap();
+                next = (next + 1) % frames.len();
+                black_box(out.len())
+            })
+        });
+        
+        // The whole frame, as sent after a resize or clear
+        group.throughput(Throughput::Bytes(average_bytes(&frames, true)));
+        group.bench_function(BenchmarkId::new("full redraw", &size), |b| {
+            let mut screen = ScreenBuffer::new();
+            let mut out = Vec::new();
+            let mut next = 0;
+            b.iter(|| {
+                out.clear();
+                screen.invalidate();
+                screen.write_frame(&frames[next], &mut out).unwrap();
+                next = (next + 1) % frames.len();
+                black_box(out.len())
+            })
+        });
+    }
+    
+    group.finish();
+}
 
 criterion_group!(
     benches,
     waffle_creation_benchmark,
     waffle_rotation_benchmark,
-    animation_update_benchmark
+    animation_update_benchmark,
+    frame_rendering_benchmark,
+    color_mode_benchmark,
+    ansi_encoding_benchmark
 );
 criterion_main!(benches);
@@ -31,7 +31,7 @@ fn main() -> Result<()> {
         animation.update(&mut waffle);
         
         // Render current frame
-        renderer.render(term, &waffle)?;
+        renderer.render(term, &waffle, &animation.frame_context())?;
         
         Ok(())
     })?;
@@ -1,8 +1,13 @@
-use std::time::{Duration, Instant};
-use crate::waffle::Waffle;
+use anyhow::{anyhow, Result};
+use clap::{builder::PossibleValue, ValueEnum};
+use std::{fmt, str::FromStr, time::Duration};
+use crate::{
+    clock::{Clock, RealClock},
+    waffle::Waffle,
+};
 
 /// Animation types for the waffle
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AnimationType {
     Rotate,
     Pulse,
@@ -10,77 +15,251 @@ pub enum AnimationType {
     Bounce,
 }
 
+impl AnimationType {
+    /// Every animation type, in the order they are cycled through
+    pub const ALL: [AnimationType; 4] = [
+        AnimationType::Rotate,
+        AnimationType::Pulse,
+        AnimationType::Wave,
+        AnimationType::Bounce,
+    ];
+    
+    /// Get the name used on the command line and in config files
+    pub fn name(self) -> &'static str {
+        match self {
+            AnimationType::Rotate => "rotate",
+            AnimationType::Pulse => "pulse",
+            AnimationType::Wave => "wave",
+            AnimationType::Bounce => "bounce",
+        }
+    }
+    
+    /// Get a short description for listings and help
+    pub fn description(self) -> &'static str {
+        match self {
+            AnimationType::Rotate => "Steady tumble around all three axes",
+            AnimationType::Pulse => "Tumble that speeds up and slows down",
+            AnimationType::Wave => "Rocking back and forth",
+            AnimationType::Bounce => "Slow turn in short bursts",
+        }
+    }
+}
+
+impl FromStr for AnimationType {
+    type Err = anyhow::Error;
+    
+    fn from_str(name: &str) -> Result<Self> {
+        let name = name.trim();
+        AnimationType::ALL
+            .into_iter()
+            .find(|animation| animation.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<_> = AnimationType::ALL.iter().map(|animation| animation.name()).collect();
+                anyhow!("Unknown animation '{}', expected one of: {}", name, names.join(", "))
+            })
+    }
+}
+
+impl ValueEnum for AnimationType {
+    fn value_variants<'a>() -> &'a [Self] {
+        &AnimationType::ALL
+    }
+    
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()).help(self.description()))
+    }
+}
+
+impl fmt::Display for AnimationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Timing information for the frame being rendered
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameContext {
+    /// Seconds since the animation started
+    pub time: f64,
+    /// Number of frames the animation has moved in so far
+    pub frame: u64,
+    /// Seconds of animation time since the previous frame
+    pub dt: f64,
+}
+
+impl FrameContext {
+    pub fn new(time: f64, frame: u64, dt: f64) -> Self {
+        Self { time, frame, dt }
+    }
+}
+
 /// Manages animations for the waffle
+///
+/// Animations are driven purely by elapsed time, so the same sequence of time
+/// steps always produces the same motion no matter how often frames are drawn.
 pub struct Animation {
     animation_type: AnimationType,
-    start_time: Instant,
+    clock: Box<dyn Clock>,
     elapsed: Duration,
+    frame: u64,
+    last_dt: Duration,
+    // Radians per second around each axis
     rotation_speed: (f64, f64, f64),
+    // Multiplier applied to every rotation
+    speed: f64,
+    paused: bool,
 }
 
 impl Animation {
     pub fn new() -> Self {
+        Self::with_clock(RealClock::new())
+    }
+    
+    /// Create an animation driven by a specific clock
+    pub fn with_clock<C: Clock + 'static>(clock: C) -> Self {
         Self {
             animation_type: AnimationType::Rotate,
-            start_time: Instant::now(),
+            clock: Box::new(clock),
             elapsed: Duration::from_secs(0),
-            rotation_speed: (0.01, 0.02, 0.003),
+            frame: 0,
+            last_dt: Duration::from_secs(0),
+            rotation_speed: (0.3, 0.6, 0.09),
+            speed: 1.0,
+            paused: false,
         }
     }
     
+    /// Replace the clock driving this animation
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+        self.clock = Box::new(clock);
+    }
+    
     /// Set the animation type
     pub fn set_animation_type(&mut self, animation_type: AnimationType) {
         self.animation_type = animation_type;
     }
     
-    /// Set the rotation speed
+    /// Set the rotation speed in radians per second
     pub fn set_rotation_speed(&mut self, x: f64, y: f64, z: f64) {
         self.rotation_speed = (x, y, z);
     }
     
+    /// Set how fast the waffle turns relative to the animation's normal pace
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.max(0.0);
+    }
+    
+    /// Get the rotation speed multiplier
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+    
+    /// Get the current animation type
+    pub fn animation_type(&self) -> AnimationType {
+        self.animation_type
+    }
+    
+    /// Pause or resume the animation
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+    
+    /// Check whether the animation is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    
     /// Get the current elapsed time in seconds
     pub fn elapsed_time(&self) -> f64 {
         self.elapsed.as_secs_f64()
     }
     
-    /// Update the waffle based on the current animation
+    /// Get the timing of the current frame for the renderer
+    pub fn frame_context(&self) -> FrameContext {
+        FrameContext::new(self.elapsed.as_secs_f64(), self.frame, self.last_dt.as_secs_f64())
+    }
+    
+    /// Start over from time zero, keeping the motion settings and the clock
+    ///
+    /// Time the clock counted before the reset is dropped.
+    pub fn reset(&mut self) {
+        self.clock.tick();
+        self.elapsed = Duration::from_secs(0);
+        self.frame = 0;
+        self.last_dt = Duration::from_secs(0);
+    }
+    
+    /// Update the waffle using the time elapsed on the animation's clock
+    ///
+    /// The clock keeps ticking while paused so that resuming doesn't jump ahead.
     pub fn update(&mut self, waffle: &mut Waffle) {
-        // Update elapsed time
-        self.elapsed = self.start_time.elapsed();
+        let dt = self.tick();
+        if !self.paused {
+            self.advance(waffle, dt);
+        }
+    }
+    
+    /// Read the time elapsed on the clock without moving the waffle
+    ///
+    /// Use this instead of [`Animation::update`] while something else controls the waffle.
+    pub fn tick(&mut self) -> Duration {
+        self.clock.tick()
+    }
+    
+    /// Advance the animation by an explicit time step, as one frame
+    pub fn advance(&mut self, waffle: &mut Waffle, dt: Duration) {
+        let (dx, dy, dz) = self.step(dt);
+        waffle.rotate(dx, dy, dz);
+        self.finish_frame(dt);
+    }
+    
+    /// Advance time by `dt` and get how far the waffle should turn around each axis
+    ///
+    /// This is [`Animation::advance`] without touching a waffle or counting a
+    /// frame, for callers that keep track of the rotation themselves and may
+    /// take several steps per frame. They call [`Animation::finish_frame`]
+    /// once per drawn frame instead.
+    pub fn step(&mut self, dt: Duration) -> (f64, f64, f64) {
+        self.elapsed += dt;
         let time = self.elapsed.as_secs_f64();
+        let dt = dt.as_secs_f64() * self.speed;
+        let (sx, sy, sz) = self.rotation_speed;
         
         match self.animation_type {
             AnimationType::Rotate => {
                 // Simple rotation animation
-                let (dx, dy, dz) = self.rotation_speed;
-                waffle.rotate(dx, dy, dz);
+                (sx * dt, sy * dt, sz * dt)
             },
             AnimationType::Pulse => {
                 // Pulsing animation that changes size over time
                 let pulse = (time.sin() * 0.5 + 0.5) * 0.2 + 0.9;
                 
                 // Rotate with variable speed based on pulse
-                let (dx, dy, dz) = self.rotation_speed;
-                waffle.rotate(dx * pulse, dy * pulse, dz * pulse);
+                (sx * pulse * dt, sy * pulse * dt, sz * pulse * dt)
             },
             AnimationType::Wave => {
                 // Wave-like animation
-                let wave_x = (time * 0.5).sin() * 0.04;
-                let wave_y = (time * 0.7).sin() * 0.03;
-                let wave_z = (time * 0.3).cos() * 0.02;
+                let wave_x = (time * 0.5).sin() * 1.2;
+                let wave_y = (time * 0.7).sin() * 0.9;
+                let wave_z = (time * 0.3).cos() * 0.6;
                 
-                waffle.rotate(wave_x, wave_y, wave_z);
+                (wave_x * dt, wave_y * dt, wave_z * dt)
             },
             AnimationType::Bounce => {
                 // Bouncing animation
                 let bounce = ((time * 2.0).sin().abs() * 0.7 + 0.3) * 0.05;
-                let (dx, dy, dz) = self.rotation_speed;
                 
-                waffle.rotate(dx * bounce, dy * bounce, dz * bounce);
+                (sx * bounce * dt, sy * bounce * dt, sz * bounce * dt)
             },
         }
     }
     
+    /// Count a frame drawn `dt` after the previous one, for the [`FrameContext`]
+    pub fn finish_frame(&mut self, dt: Duration) {
+        self.frame += 1;
+        self.last_dt = dt;
+    }
+    
     /// Cycle to the next animation type
     pub fn next_animation(&mut self) {
         self.animation_type = match self.animation_type {
@@ -90,7 +269,13 @@ impl Animation {
             AnimationType::Bounce => AnimationType::Rotate,
         };
         
-        // Reset start time for the new animation
-        self.start_time = Instant::now();
+        // Restart the time for the new animation
+        self.elapsed = Duration::from_secs(0);
+    }
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -0,0 +1,454 @@
+use anyhow::Result;
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use std::{f64::consts::PI, time::Duration};
+
+use crate::{
+    animation::Animation,
+    camera::Camera,
+    event_loop::{AppEvent, Control, EventHandler},
+    framebuffer::RenderTarget,
+    governor::{Governor, Quality},
+    input::{Action, KeyMap},
+    renderer::{Renderer, Stage, TimingAverage},
+    rotation::Rotation,
+    timestep::{FixedTimestep, FrameStats, DEFAULT_UPDATE_RATE},
+    trackball::Trackball,
+    waffle::Waffle,
+};
+
+/// Lowest frame rate reachable with the frame rate keys
+pub const MIN_FPS: u32 = 1;
+
+/// Highest frame rate reachable with the frame rate keys
+pub const MAX_FPS: u32 = 120;
+
+/// How much the frame rate keys change the frame rate
+const FPS_STEP: u32 = 5;
+
+/// Factor the speed keys multiply or divide the rotation speed by
+const SPEED_STEP: f64 = 1.25;
+
+/// Slowest rotation speed multiplier
+pub const MIN_SPEED: f64 = 0.05;
+
+/// Fastest rotation speed multiplier
+pub const MAX_SPEED: f64 = 20.0;
+
+/// How much one step of the mouse wheel zooms in or out
+const WHEEL_ZOOM: f64 = 1.1;
+
+/// The interactive visualizer: the waffle, how it moves and how it's drawn
+///
+/// Keys are translated into [`Action`]s through a [`KeyMap`], so the app can
+/// be driven by a terminal or directly from code. Mouse drags take over from
+/// the animation until the waffle stops spinning.
+///
+/// The animation runs on a fixed timestep independent of the frame rate, and
+/// frames show the waffle interpolated between the last two updates.
+///
+/// With adaptive quality on, a [`Governor`] watches how long frames take and
+/// turns effects, mesh detail and finally the frame rate down to keep up.
+pub struct App {
+    waffle: Waffle,
+    renderer: Renderer,
+    animation: Animation,
+    keymap: KeyMap,
+    trackball: Trackball,
+    timestep: FixedTimestep,
+    stats: FrameStats,
+    profile: TimingAverage,
+    governor: Option<Governor>,
+    // Whether bloom was on before the governor took over
+    bloom_enabled: bool,
+    // Simulated rotation after the latest update and the turn made by that update
+    rotation: (f64, f64, f64),
+    last_step: (f64, f64, f64),
+    // Fraction of an update between the latest update and the time being drawn
+    alpha: f64,
+    fps: u32,
+    show_help: bool,
+    show_stats: bool,
+    show_profile: bool,
+    quit: bool,
+    // Camera to return to when the view is reset
+    initial_camera: Camera,
+}
+
+impl App {
+    pub fn new(waffle: Waffle, renderer: Renderer, animation: Animation, keymap: KeyMap, fps: u32) -> Self {
+        let initial_camera = renderer.camera().clone();
+        let rotation = waffle.rotation();
+        let bloom_enabled = renderer.bloom().enabled;
+        Self {
+            waffle,
+            renderer,
+            animation,
+            keymap,
+            trackball: Trackball::new(),
+            timestep: FixedTimestep::from_rate(DEFAULT_UPDATE_RATE),
+            stats: FrameStats::new(),
+            profile: TimingAverage::new(),
+            governor: None,
+            bloom_enabled,
+            rotation,
+            last_step: (0.0, 0.0, 0.0),
+            alpha: 1.0,
+            fps: fps.clamp(MIN_FPS, MAX_FPS),
+            show_help: false,
+            show_stats: false,
+            show_profile: false,
+            quit: false,
+            initial_camera,
+        }
+    }
+    
+    /// Get the waffle
+    pub fn waffle(&self) -> &Waffle {
+        &self.waffle
+    }
+    
+    /// Get the renderer
+    pub fn renderer(&self) -> &Renderer {
+        &self.renderer
+    }
+    
+    /// Get the animation
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+    
+    /// Get the key bindings
+    pub fn keymap(&self) -> &KeyMap {
+        &self.keymap
+    }
+    
+    /// Get the mouse rotation state
+    pub fn trackball(&self) -> &Trackball {
+        &self.trackball
+    }
+    
+    /// Get the fixed timestep the animation runs on
+    pub fn timestep(&self) -> &FixedTimestep {
+        &self.timestep
+    }
+    
+    /// Set the number of animation updates per second
+    pub fn set_update_rate(&mut self, rate: u32) {
+        self.timestep = FixedTimestep::from_rate(rate);
+    }
+    
+    /// Get the measured frame rate
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+    
+    /// Show or hide the frame rate overlay
+    pub fn set_stats_visible(&mut self, visible: bool) {
+        self.show_stats = visible;
+    }
+    
+    /// Check whether the frame rate overlay is shown
+    pub fn is_stats_visible(&self) -> bool {
+        self.show_stats
+    }
+    
+    /// Get the smoothed time spent in each render stage
+    pub fn profile(&self) -> &TimingAverage {
+        &self.profile
+    }
+    
+    /// Show or hide the render stage timings
+    pub fn set_profile_visible(&mut self, visible: bool) {
+        self.show_profile = visible;
+    }
+    
+    /// Check whether the render stage timings are shown
+    pub fn is_profile_visible(&self) -> bool {
+        self.show_profile
+    }
+    
+    /// Turn adaptive quality on or off
+    ///
+    /// Turning it off restores full quality.
+    pub fn set_adaptive(&mut self, adaptive: bool) {
+        if adaptive == self.governor.is_some() {
+            return;
+        }
+        if adaptive {
+            self.governor = Some(Governor::new(self.fps));
+        } else {
+            self.governor = None;
+            self.apply_quality(Quality::FULL);
+        }
+    }
+    
+    /// Get the quality governor, if adaptive quality is on
+    pub fn governor(&self) -> Option<&Governor> {
+        self.governor.as_ref()
+    }
+    
+    /// Get the quality frames are currently drawn at
+    pub fn quality(&self) -> Quality {
+        self.governor.as_ref().map_or(Quality::FULL, Governor::quality)
+    }
+    
+    /// Get the target frame rate
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
+    
+    /// Get the frame rate actually aimed for, which the governor may have lowered
+    pub fn effective_fps(&self) -> u32 {
+        ((self.fps as f64 * self.quality().fps_scale).round() as u32).max(MIN_FPS)
+    }
+    
+    /// Get the time between frames at the effective frame rate
+    pub fn frame_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.effective_fps() as f64)
+    }
+    
+    /// Check whether the key binding help is shown
+    pub fn is_help_visible(&self) -> bool {
+        self.show_help
+    }
+    
+    /// Check whether the user asked to quit
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+    
+    /// Handle a key press, returning the action it triggered
+    pub fn handle_key(&mut self, key: &KeyEvent) -> Option<Action> {
+        let action = self.keymap.action_for_event(key)?;
+        self.handle_action(action);
+        Some(action)
+    }
+    
+    /// Handle a mouse event: drag to rotate, scroll to zoom
+    pub fn handle_mouse(&mut self, mouse: &MouseEvent) {
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.trackball.press(mouse.column, mouse.row),
+            MouseEventKind::Drag(MouseButton::Left) => {
+                let height = self.renderer.frame().height();
+                self.trackball
+                    .drag(&mut self.waffle, self.renderer.camera(), mouse.column, mouse.row, height);
+                self.sync_rotation();
+            },
+            MouseEventKind::Up(MouseButton::Left) => self.trackball.release(),
+            MouseEventKind::ScrollUp => self.renderer.zoom(WHEEL_ZOOM),
+            MouseEventKind::ScrollDown => self.renderer.zoom(1.0 / WHEEL_ZOOM),
+            _ => {},
+        }
+    }
+    
+    /// Perform an action
+    pub fn handle_action(&mut self, action: Action) {
+        match action {
+            Action::Quit => self.quit = true,
+            Action::TogglePause => {
+                let paused = self.animation.is_paused();
+                self.animation.set_paused(!paused);
+            },
+            Action::Step => {
+                // Stepping only makes sense on a still image
+                self.animation.set_paused(true);
+                let dt = self.frame_duration();
+                self.animation.advance(&mut self.waffle, dt);
+                self.sync_rotation();
+            },
+            Action::SpeedUp => self.scale_speed(SPEED_STEP),
+            Action::SlowDown => self.scale_speed(1.0 / SPEED_STEP),
+            Action::FpsUp => self.set_fps((self.fps + FPS_STEP).min(MAX_FPS)),
+            Action::FpsDown => self.set_fps(self.fps.saturating_sub(FPS_STEP).max(MIN_FPS)),
+            Action::NextAnimation => self.animation.next_animation(),
+            Action::NextColor => {
+                let mode = self.renderer.color_mode().next();
+                self.renderer.set_color_mode(mode);
+            },
+            Action::NextSize => {
+                self.replace_waffle(Waffle::with_detail(self.waffle.size().next(), self.waffle.detail()));
+                self.sync_rotation();
+            },
+            Action::ResetView => {
+                self.trackball.stop();
+                self.waffle.set_rotation(0.0, 0.0, 0.0);
+                self.waffle.set_view_turn(Rotation::IDENTITY);
+                self.animation.set_speed(1.0);
+                self.renderer.set_camera(self.initial_camera.clone());
+                self.sync_rotation();
+            },
+            Action::ReleaseControl => self.trackball.stop(),
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::ToggleStats => self.show_stats = !self.show_stats,
+            Action::ToggleProfile => self.show_profile = !self.show_profile,
+        }
+    }
+    
+    fn set_fps(&mut self, fps: u32) {
+        self.fps = fps;
+        if let Some(governor) = &mut self.governor {
+            governor.set_target_fps(fps);
+        }
+    }
+    
+    fn scale_speed(&mut self, factor: f64) {
+        let speed = (self.animation.speed() * factor).clamp(MIN_SPEED, MAX_SPEED);
+        self.animation.set_speed(speed);
+    }
+    
+    /// Switch effects and mesh detail to a quality level
+    fn apply_quality(&mut self, quality: Quality) {
+        let mut bloom = self.renderer.bloom().clone();
+        bloom.enabled = self.bloom_enabled && quality.bloom;
+        self.renderer.set_bloom(bloom);
+        self.renderer.set_color_depth(quality.color_depth);
+        
+        if self.waffle.detail() != quality.detail {
+            self.replace_waffle(Waffle::with_detail(self.waffle.size(), quality.detail));
+        }
+    }
+    
+    /// Swap in a new model but keep it facing the same way
+    fn replace_waffle(&mut self, mut waffle: Waffle) {
+        let (x, y, z) = self.waffle.rotation();
+        waffle.set_rotation(x, y, z);
+        waffle.set_view_turn(self.waffle.view_turn());
+        self.waffle = waffle;
+    }
+    
+    /// Take the waffle's current rotation as the simulation state, e.g. after the mouse moved it
+    fn sync_rotation(&mut self) {
+        self.rotation = self.waffle.rotation();
+        self.last_step = (0.0, 0.0, 0.0);
+        self.alpha = 1.0;
+    }
+    
+    /// Advance the animation by the time elapsed on its clock, unless the mouse is in control
+    ///
+    /// The elapsed time is simulated in fixed steps; a frame that arrives too
+    /// late runs a limited number of them and skips the rest.
+    pub fn update(&mut self) {
+        let dt = self.animation.tick();
+        self.stats.record_frame(dt);
+        
+        if self.trackball.is_active() {
+            self.trackball.update(&mut self.waffle, dt);
+            self.sync_rotation();
+            return;
+        }
+        if self.animation.is_paused() {
+            // Don't make up for the time spent paused
+            self.timestep.reset();
+            return;
+        }
+        
+        let steps = self.timestep.accumulate(dt);
+        for _ in 0..steps.count {
+            let (dx, dy, dz) = self.animation.step(self.timestep.step());
+            let (x, y, z) = self.rotation;
+            self.rotation = ((x + dx) % (2.0 * PI), (y + dy) % (2.0 * PI), (z + dz) % (2.0 * PI));
+            self.last_step = (dx, dy, dz);
+        }
+        self.alpha = steps.alpha;
+        self.animation.finish_frame(dt);
+        
+        // Show the waffle part of the way from the previous update to the latest one
+        let (x, y, z) = self.rotation;
+        let (dx, dy, dz) = self.last_step;
+        let lag = 1.0 - self.alpha;
+        self.waffle.set_rotation(x - dx * lag, y - dy * lag, z - dz * lag);
+    }
+    
+    /// Render the current frame, with the help or pause notice on top, to a target
+    pub fn render<T: RenderTarget + ?Sized>(&mut self, target: &mut T) -> Result<()> {
+        let mut overlay = Vec::new();
+        if self.show_stats {
+            overlay.push(format!(
+                "FPS {:.1}/{}  updates {:.0}/s  skipped {}",
+                self.stats.fps(),
+                self.effective_fps(),
+                self.timestep.rate(),
+                self.timestep.total_skipped(),
+            ));
+            // What the previous frame cost to send, which matters more than CPU time over a slow link
+            if let Some(output) = self.renderer.output_stats() {
+                overlay.push(format!(
+                    "output {} B/frame  {:.1} KiB/s  escapes {}  cells {}",
+                    output.bytes,
+                    output.bytes as f64 * self.stats.fps() / 1024.0,
+                    output.escapes,
+                    output.cells_changed,
+                ));
+            }
+            if let Some(governor) = &self.governor {
+                overlay.push(format!(
+                    "quality {}/{}: {}  frame {:.1}ms",
+                    governor.level(),
+                    governor.max_level(),
+                    governor.quality(),
+                    governor.average_cost().as_secs_f64() * 1000.0,
+                ));
+            }
+        }
+        if self.show_profile {
+            // Timings of the previous frame, since this one hasn't been drawn yet
+            let stages: Vec<_> = Stage::ALL
+                .iter()
+                .map(|&stage| format!("{} {:.2}", stage, self.profile.stage(stage).as_secs_f64() * 1000.0))
+                .collect();
+            overlay.push(format!("ms: {}", stages.join("  ")));
+        }
+        if self.show_help {
+            overlay.extend(["Keys".to_string(), String::new()]);
+            overlay.extend(self.keymap.help_lines());
+        } else if self.animation.is_paused() {
+            overlay.push(match self.keymap.keys_for(Action::ToggleHelp).next() {
+                Some(key) => format!("Paused - press {} for help", key),
+                None => "Paused".to_string(),
+            });
+        }
+        self.renderer.set_overlay(overlay);
+        
+        // Colors follow the same interpolated time as the rotation
+        let mut ctx = self.animation.frame_context();
+        ctx.time = (ctx.time - self.timestep.step().as_secs_f64() * (1.0 - self.alpha)).max(0.0);
+        
+        self.renderer.render(target, &self.waffle, &ctx)?;
+        
+        let timing = self.renderer.timing();
+        self.profile.record(&timing);
+        if let Some(governor) = &mut self.governor {
+            if let Some(quality) = governor.record(timing.render(), timing.emit) {
+                self.apply_quality(quality);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: RenderTarget + ?Sized> EventHandler<T> for App {
+    fn handle_event(&mut self, event: AppEvent, target: &mut T) -> Result<Control> {
+        let frame_duration = self.frame_duration();
+        match event {
+            AppEvent::Key(key) => {
+                self.handle_key(&key);
+            },
+            AppEvent::Mouse(mouse) => self.handle_mouse(&mouse),
+            AppEvent::Tick => {
+                self.update();
+                self.render(target)?;
+            },
+            // The button release may never arrive once the window loses focus
+            AppEvent::FocusLost => self.trackball.release(),
+            AppEvent::Resize(..) | AppEvent::FocusGained => {},
+        }
+        
+        if self.quit {
+            Ok(Control::Quit)
+        } else if self.frame_duration() != frame_duration {
+            Ok(Control::SetFrameDuration(self.frame_duration()))
+        } else {
+            Ok(Control::Continue)
+        }
+    }
+}
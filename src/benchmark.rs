@@ -1,152 +1,744 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use clap::{builder::PossibleValue, ValueEnum};
+use serde::{de, ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer};
 use std::{
-    io::{self, Write},
+    collections::HashMap,
+    fmt,
+    fs,
+    io::Write,
+    path::Path,
+    str::FromStr,
     time::{Duration, Instant},
 };
-use crossterm::{
-    cursor::MoveTo,
-    execute,
-    style::{Color, Print, SetForegroundColor},
-};
 
 use crate::{
-    animation::Animation,
-    renderer::Renderer,
-    terminal::Terminal,
+    animation::{Animation, FrameContext},
+    framebuffer::RenderTarget,
+    renderer::{ColorMode, FrameTiming, Renderer, Stage},
+    screen::OutputStats,
     waffle::{Size, Waffle},
 };
 
-/// Number of frames to render in benchmark
-const BENCHMARK_FRAMES: usize = 100;
+/// Default number of frames to render per waffle size and color mode
+pub const BENCHMARK_FRAMES: usize = 100;
 
-/// Run a performance benchmark
-pub fn run_benchmark(
-    term: &mut Terminal,
-    renderer: &mut Renderer,
-    waffle: &mut Waffle,
-    animation: &mut Animation,
-) -> Result<()> {
-    println!("Running benchmark...");
-    
-    // First, warm up the system
-    for _ in 0..10 {
-        animation.update(waffle);
-        renderer.render(term, waffle)?;
+/// Default number of untimed frames rendered before each run
+pub const WARMUP_FRAMES: usize = 10;
+
+/// Version of the JSON report layout, raised whenever results gain or change fields
+///
+/// Baselines saved with another version aren't comparable and are rejected.
+pub const REPORT_VERSION: u32 = 1;
+
+/// What to render and how often
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkOptions {
+    /// Timed frames per run
+    pub frames: usize,
+    /// Untimed frames rendered before each run
+    pub warmup: usize,
+    /// Waffle sizes to benchmark
+    pub sizes: Vec<Size>,
+    /// Color modes to benchmark with every size
+    pub color_modes: Vec<ColorMode>,
+}
+
+impl Default for BenchmarkOptions {
+    fn default() -> Self {
+        Self {
+            frames: BENCHMARK_FRAMES,
+            warmup: WARMUP_FRAMES,
+            sizes: Size::ALL.to_vec(),
+            color_modes: ColorMode::ALL.to_vec(),
+        }
     }
-    
-    // Benchmark different waffle sizes
-    let sizes = [Size::Small, Size::Medium, Size::Large];
-    let size_names = ["Small", "Medium", "Large"];
-    
-    for (i, &size) in sizes.iter().enumerate() {
-        // Create a new waffle with the current size
-        let mut test_waffle = Waffle::new(size);
+}
+
+/// Summary of a set of frame times, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct FrameStats {
+    pub frames: usize,
+    pub mean_ms: f64,
+    pub stddev_ms: f64,
+    pub min_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    /// Frames per second at the mean frame time
+    pub fps: f64,
+}
+
+impl FrameStats {
+    /// Summarize frame times; all zero when there are none
+    pub fn from_samples(samples: &[Duration]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
         
-        // Set initial rotation
-        test_waffle.set_rotation(0.5, 0.2, 0.1);
+        let mut millis: Vec<f64> = samples.iter().map(|sample| sample.as_secs_f64() * 1000.0).collect();
+        millis.sort_by(f64::total_cmp);
         
-        // Time the rendering of frames
-        let start = Instant::now();
-        let mut frame_times = Vec::with_capacity(BENCHMARK_FRAMES);
+        let count = millis.len() as f64;
+        let mean = millis.iter().sum::<f64>() / count;
+        let variance = millis.iter().map(|ms| (ms - mean).powi(2)).sum::<f64>() / count;
         
-        for _ in 0..BENCHMARK_FRAMES {
-            let frame_start = Instant::now();
-            
-            // Update animation
-            animation.update(&mut test_waffle);
+        Self {
+            frames: millis.len(),
+            mean_ms: mean,
+            stddev_ms: variance.sqrt(),
+            min_ms: millis[0],
+            p50_ms: percentile(&millis, 50.0),
+            p95_ms: percentile(&millis, 95.0),
+            p99_ms: percentile(&millis, 99.0),
+            max_ms: millis[millis.len() - 1],
+            fps: if mean > 0.0 { 1000.0 / mean } else { 0.0 },
+        }
+    }
+    
+    /// Get the frame time a metric looks at
+    pub fn metric(&self, metric: Metric) -> f64 {
+        match metric {
+            Metric::Mean => self.mean_ms,
+            Metric::P50 => self.p50_ms,
+            Metric::P95 => self.p95_ms,
+            Metric::P99 => self.p99_ms,
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Time spent in one stage of the frame, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct StageStats {
+    #[serde(skip)]
+    pub stage: Stage,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    /// Fraction of the summed stage means spent in this stage
+    pub share: f64,
+}
+
+impl StageStats {
+    /// Summarize every stage of a set of frame timings
+    pub fn from_timings(timings: &[FrameTiming]) -> Vec<Self> {
+        let mut stages: Vec<Self> = Stage::ALL
+            .iter()
+            .map(|&stage| {
+                let samples: Vec<Duration> = timings.iter().map(|timing| timing.stage(stage)).collect();
+                let stats = FrameStats::from_samples(&samples);
+                Self {
+                    stage,
+                    mean_ms: stats.mean_ms,
+                    p50_ms: stats.p50_ms,
+                    p95_ms: stats.p95_ms,
+                    share: 0.0,
+                }
+            })
+            .collect();
+        
+        let total: f64 = stages.iter().map(|stats| stats.mean_ms).sum();
+        if total > 0.0 {
+            for stats in &mut stages {
+                stats.share = stats.mean_ms / total;
+            }
+        }
+        stages
+    }
+}
+
+/// Terminal output written per frame, for targets that encode frames
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutputSummary {
+    pub bytes_per_frame: f64,
+    pub max_bytes: u64,
+    pub escapes_per_frame: f64,
+    pub cells_changed_per_frame: f64,
+}
+
+impl OutputSummary {
+    /// Average the output of a set of frames; `None` when there are none
+    pub fn from_frames(frames: &[OutputStats]) -> Option<Self> {
+        if frames.is_empty() {
+            return None;
+        }
+        
+        let count = frames.len() as f64;
+        let mean = |value: fn(&OutputStats) -> u64| frames.iter().map(value).sum::<u64>() as f64 / count;
+        Some(Self {
+            bytes_per_frame: mean(|output| output.bytes),
+            max_bytes: frames.iter().map(|output| output.bytes).max().unwrap_or(0),
+            escapes_per_frame: mean(|output| output.escapes),
+            cells_changed_per_frame: mean(|output| output.cells_changed),
+        })
+    }
+}
+
+/// Frame times of one waffle size rendered in one color mode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    #[serde(serialize_with = "display", deserialize_with = "parsed")]
+    pub size: Size,
+    #[serde(serialize_with = "display", deserialize_with = "parsed")]
+    pub color_mode: ColorMode,
+    #[serde(flatten)]
+    pub stats: FrameStats,
+    /// Breakdown of the frame time, in [`Stage::ALL`] order
+    #[serde(default, serialize_with = "stage_map", deserialize_with = "stage_list")]
+    pub stages: Vec<StageStats>,
+    /// Output written per frame, if the target encodes frames
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<OutputSummary>,
+}
+
+impl BenchmarkResult {
+    /// Get the breakdown of one stage
+    pub fn stage(&self, stage: Stage) -> Option<&StageStats> {
+        self.stages.iter().find(|stats| stats.stage == stage)
+    }
+}
+
+/// Results of a whole benchmark
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    /// Layout version, always [`REPORT_VERSION`] when written by this version
+    pub version: u32,
+    pub width: u16,
+    pub height: u16,
+    pub frames: usize,
+    pub warmup: usize,
+    pub results: Vec<BenchmarkResult>,
+}
+
+impl BenchmarkReport {
+    /// Parse a report written in the JSON format
+    pub fn parse(text: &str) -> Result<Self> {
+        let report: Self = serde_json::from_str(text)?;
+        if report.version != REPORT_VERSION {
+            bail!(
+                "Report format version {} is not supported (expected {}); save the baseline again",
+                report.version,
+                REPORT_VERSION
+            );
+        }
+        Ok(report)
+    }
+    
+    /// Read a report saved as a baseline
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read baseline {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid baseline {}", path.display()))
+    }
+    
+    /// Save the report as a baseline for later runs to compare against
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut text = serde_json::to_string_pretty(self)?;
+        text.push('\n');
+        fs::write(path, text).with_context(|| format!("Failed to write baseline {}", path.display()))
+    }
+    
+    /// Find the result for a size and color mode
+    pub fn result(&self, size: Size, color_mode: ColorMode) -> Option<&BenchmarkResult> {
+        self.results
+            .iter()
+            .find(|result| result.size == size && result.color_mode == color_mode)
+    }
+}
+
+fn display<T: fmt::Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// Deserialize a value through `FromStr`
+fn parsed<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let text = String::deserialize(deserializer)?;
+    text.parse().map_err(de::Error::custom)
+}
+
+/// Read stages back from an object keyed by stage name, ignoring stages this version doesn't know
+fn stage_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<StageStats>, D::Error> {
+    #[derive(Deserialize)]
+    struct Entry {
+        mean_ms: f64,
+        p50_ms: f64,
+        p95_ms: f64,
+        share: f64,
+    }
+    
+    let mut entries = HashMap::<String, Entry>::deserialize(deserializer)?;
+    Ok(Stage::ALL
+        .iter()
+        .filter_map(|&stage| {
+            let entry = entries.remove(stage.name())?;
+            Some(StageStats {
+                stage,
+                mean_ms: entry.mean_ms,
+                p50_ms: entry.p50_ms,
+                p95_ms: entry.p95_ms,
+                share: entry.share,
+            })
+        })
+        .collect())
+}
+
+/// Write stages as an object keyed by stage name, so they can be looked up without searching
+fn stage_map<S: Serializer>(stages: &[StageStats], serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(stages.len()))?;
+    for stats in stages {
+        map.serialize_entry(stats.stage.name(), stats)?;
+    }
+    map.end()
+}
+
+/// Render every size in every color mode and time each frame
+///
+/// Frames are rendered back to back without waiting for input, so the run can be
+/// scripted. Give the animation a fixed-step clock to render the same frames every time.
+/// The animation starts over for every size and color mode, so each result is the
+/// same whether it's measured alone or as part of a sweep.
+pub fn run<T: RenderTarget + ?Sized>(
+    target: &mut T,
+    renderer: &mut Renderer,
+    animation: &mut Animation,
+    options: &BenchmarkOptions,
+) -> Result<BenchmarkReport> {
+    let original_mode = renderer.color_mode();
+    let (width, height) = target.size();
+    let mut results = Vec::with_capacity(options.sizes.len() * options.color_modes.len());
+    
+    for &size in &options.sizes {
+        for &color_mode in &options.color_modes {
+            renderer.set_color_mode(color_mode);
+            let mut waffle = Waffle::new(size);
+            waffle.set_rotation(0.5, 0.2, 0.1);
+            animation.reset();
             
-            // Render frame
-            renderer.render(term, &test_waffle)?;
+            for _ in 0..options.warmup {
+                animation.update(&mut waffle);
+                renderer.render(target, &waffle, &animation.frame_context())?;
+            }
             
-            // Record frame time
-            frame_times.push(frame_start.elapsed());
+            let mut samples = Vec::with_capacity(options.frames);
+            let mut timings = Vec::with_capacity(options.frames);
+            let mut output = Vec::new();
+            for _ in 0..options.frames {
+                let start = Instant::now();
+                animation.update(&mut waffle);
+                renderer.render(target, &waffle, &animation.frame_context())?;
+                samples.push(start.elapsed());
+                timings.push(renderer.timing());
+                output.extend(renderer.output_stats());
+            }
             
-            // Small delay to avoid terminal issues
-            std::thread::sleep(Duration::from_millis(5));
+            results.push(BenchmarkResult {
+                size,
+                color_mode,
+                stats: FrameStats::from_samples(&samples),
+                stages: StageStats::from_timings(&timings),
+                output: OutputSummary::from_frames(&output),
+            });
+        }
+    }
+    
+    renderer.set_color_mode(original_mode);
+    Ok(BenchmarkReport {
+        version: REPORT_VERSION,
+        width,
+        height,
+        frames: options.frames,
+        warmup: options.warmup,
+        results,
+    })
+}
+
+/// Frame time statistic compared against a baseline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Mean,
+    P50,
+    P95,
+    P99,
+}
+
+impl Metric {
+    /// Every metric
+    pub const ALL: [Metric; 4] = [Metric::Mean, Metric::P50, Metric::P95, Metric::P99];
+    
+    /// Get the name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Mean => "mean",
+            Metric::P50 => "p50",
+            Metric::P95 => "p95",
+            Metric::P99 => "p99",
+        }
+    }
+    
+    /// Get a short description for listings and help
+    pub fn description(self) -> &'static str {
+        match self {
+            Metric::Mean => "Average frame time",
+            Metric::P50 => "Median frame time, the least sensitive to outliers",
+            Metric::P95 => "Frame time 95% of frames stay under",
+            Metric::P99 => "Frame time 99% of frames stay under, dominated by hiccups",
+        }
+    }
+}
+
+impl ValueEnum for Metric {
+    fn value_variants<'a>() -> &'a [Self] {
+        &Metric::ALL
+    }
+    
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()).help(self.description()))
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// One result measured against its baseline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delta {
+    pub size: Size,
+    pub color_mode: ColorMode,
+    pub baseline_ms: f64,
+    pub current_ms: f64,
+}
+
+impl Delta {
+    /// Get the relative change, e.g. 0.1 for 10% slower and -0.1 for 10% faster
+    ///
+    /// Any time taken over a baseline of zero is an infinite slowdown.
+    pub fn change(&self) -> f64 {
+        if self.baseline_ms > 0.0 {
+            self.current_ms / self.baseline_ms - 1.0
+        } else if self.current_ms > 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A benchmark report measured against a saved baseline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub metric: Metric,
+    /// Relative slowdown tolerated before a result counts as a regression, e.g. 0.1 for 10%
+    pub threshold: f64,
+    /// Results found in both reports, in the order of the current report
+    pub deltas: Vec<Delta>,
+    /// Results of the current report the baseline doesn't have
+    pub unmatched: usize,
+}
+
+impl Comparison {
+    /// Compare every result of `current` with the same size and color mode in `baseline`
+    pub fn new(baseline: &BenchmarkReport, current: &BenchmarkReport, metric: Metric, threshold: f64) -> Self {
+        let mut deltas = Vec::with_capacity(current.results.len());
+        let mut unmatched = 0;
+        for result in &current.results {
+            match baseline.result(result.size, result.color_mode) {
+                Some(base) => deltas.push(Delta {
+                    size: result.size,
+                    color_mode: result.color_mode,
+                    baseline_ms: base.stats.metric(metric),
+                    current_ms: result.stats.metric(metric),
+                }),
+                None => unmatched += 1,
+            }
         }
         
-        // Calculate statistics
-        let total_time = start.elapsed();
-        let fps = BENCHMARK_FRAMES as f64 / total_time.as_secs_f64();
-        
-        let avg_frame_time: Duration = frame_times.iter().sum::<Duration>() / frame_times.len() as u32;
-        
-        // Find min and max frame times - create owned values to fix temporary value issue
-        let zero_duration = Duration::from_secs(0);
-        let min_frame_time = frame_times.iter().min().unwrap_or(&zero_duration).clone();
-        let max_frame_time = frame_times.iter().max().unwrap_or(&zero_duration).clone();
-        
-        // Clear terminal for results
-        term.clear()?;
-        
-        // Print benchmark results
-        let stdout = io::stdout();
-        let mut stdout = stdout.lock();
-        
-        execute!(
-            stdout,
-            MoveTo(2, 2),
-            SetForegroundColor(Color::Green),
-            Print(format!("Benchmark Results for {} Waffle:", size_names[i]))
-        )?;
-        
-        execute!(
-            stdout,
-            MoveTo(2, 4),
-            SetForegroundColor(Color::White),
-            Print(format!("Total time: {:.2} seconds", total_time.as_secs_f64()))
-        )?;
-        
-        execute!(
-            stdout,
-            MoveTo(2, 5),
-            SetForegroundColor(Color::White),
-            Print(format!("Average FPS: {:.2}", fps))
-        )?;
+        Self {
+            metric,
+            threshold,
+            deltas,
+            unmatched,
+        }
+    }
+    
+    /// Check whether a result slowed down by more than the threshold
+    pub fn is_regression(&self, delta: &Delta) -> bool {
+        delta.change() > self.threshold
+    }
+    
+    /// Iterate over the results that slowed down by more than the threshold
+    pub fn regressions(&self) -> impl Iterator<Item = &Delta> {
+        self.deltas.iter().filter(|delta| self.is_regression(delta))
+    }
+    
+    /// Fail on regressions, on results missing from the baseline unless `allow_missing`, or if nothing was compared
+    pub fn check(&self, allow_missing: bool) -> Result<()> {
+        if self.deltas.is_empty() {
+            bail!("None of the results are in the baseline, so nothing was compared");
+        }
         
-        execute!(
-            stdout,
-            MoveTo(2, 6),
-            SetForegroundColor(Color::White),
-            Print(format!("Average frame time: {:.2} ms", avg_frame_time.as_micros() as f64 / 1000.0))
+        let regressions = self.regressions().count();
+        if regressions > 0 {
+            bail!(
+                "{} of {} results are more than {:.1}% slower than the baseline",
+                regressions,
+                self.deltas.len(),
+                self.threshold * 100.0
+            );
+        }
+        if self.unmatched > 0 && !allow_missing {
+            bail!(
+                "{} results have no baseline to compare with (use --allow-missing to skip them)",
+                self.unmatched
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Write a comparison as a table, marking regressions
+pub fn write_comparison<W: Write>(comparison: &Comparison, out: &mut W) -> Result<()> {
+    writeln!(
+        out,
+        "{} frame time against the baseline, regression above +{:.1}%",
+        comparison.metric,
+        comparison.threshold * 100.0
+    )?;
+    writeln!(out)?;
+    writeln!(out, "{:<8} {:<8} {:>11} {:>11} {:>8}", "size", "color", "baseline ms", "current ms", "change")?;
+    for delta in &comparison.deltas {
+        writeln!(
+            out,
+            "{:<8} {:<8} {:>11.3} {:>11.3} {:>+7.1}%{}",
+            delta.size.name(),
+            delta.color_mode.name(),
+            delta.baseline_ms,
+            delta.current_ms,
+            delta.change() * 100.0,
+            if comparison.is_regression(delta) { "  REGRESSION" } else { "" }
         )?;
-        
-        execute!(
-            stdout,
-            MoveTo(2, 7),
-            SetForegroundColor(Color::White),
-            Print(format!("Min frame time: {:.2} ms", min_frame_time.as_micros() as f64 / 1000.0))
+    }
+    if comparison.unmatched > 0 {
+        writeln!(out, "{} results have no baseline to compare with", comparison.unmatched)?;
+    }
+    Ok(())
+}
+
+/// Output format of a benchmark report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Aligned columns for reading
+    Table,
+    /// A single JSON object
+    Json,
+    /// One row per size and color mode, with a header
+    Csv,
+}
+
+impl ReportFormat {
+    /// Every report format
+    pub const ALL: [ReportFormat; 3] = [ReportFormat::Table, ReportFormat::Json, ReportFormat::Csv];
+    
+    /// Get the name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            ReportFormat::Table => "table",
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+        }
+    }
+    
+    /// Get a short description for listings and help
+    pub fn description(self) -> &'static str {
+        match self {
+            ReportFormat::Table => "Aligned columns for reading in a terminal",
+            ReportFormat::Json => "JSON object with the settings and every result",
+            ReportFormat::Csv => "Comma-separated values with a header row",
+        }
+    }
+    
+    /// Guess the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(ReportFormat::Json),
+            "csv" => Some(ReportFormat::Csv),
+            "txt" | "text" => Some(ReportFormat::Table),
+            _ => None,
+        }
+    }
+}
+
+impl ValueEnum for ReportFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &ReportFormat::ALL
+    }
+    
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()).help(self.description()))
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Header row of the CSV output, followed by the mean time of every stage and the output columns
+const COLUMNS: [&str; 11] = [
+    "size", "color_mode", "frames", "mean_ms", "stddev_ms", "min_ms", "p50_ms", "p95_ms", "p99_ms", "max_ms", "fps",
+];
+
+/// Output columns at the end of the CSV output
+const OUTPUT_COLUMNS: [&str; 4] = ["bytes_per_frame", "max_bytes", "escapes_per_frame", "cells_changed_per_frame"];
+
+/// Write a report in the given format
+pub fn write_report<W: Write>(report: &BenchmarkReport, format: ReportFormat, out: &mut W) -> Result<()> {
+    match format {
+        ReportFormat::Table => write_table(report, out)?,
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, report)?;
+            writeln!(out)?;
+        },
+        ReportFormat::Csv => write_csv(report, out)?,
+    }
+    Ok(())
+}
+
+fn write_table<W: Write>(report: &BenchmarkReport, out: &mut W) -> Result<()> {
+    writeln!(
+        out,
+        "{}x{} cells, {} frames per run after {} warmup frames",
+        report.width, report.height, report.frames, report.warmup
+    )?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{:<8} {:<8} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "size", "color", "mean ms", "stddev", "min", "p50", "p95", "p99", "max", "fps"
+    )?;
+    for result in &report.results {
+        let stats = &result.stats;
+        writeln!(
+            out,
+            "{:<8} {:<8} {:>9.3} {:>9.3} {:>9.3} {:>9.3} {:>9.3} {:>9.3} {:>9.3} {:>9.1}",
+            result.size.name(),
+            result.color_mode.name(),
+            stats.mean_ms,
+            stats.stddev_ms,
+            stats.min_ms,
+            stats.p50_ms,
+            stats.p95_ms,
+            stats.p99_ms,
+            stats.max_ms,
+            stats.fps
         )?;
-        
-        execute!(
-            stdout,
-            MoveTo(2, 8),
-            SetForegroundColor(Color::White),
-            Print(format!("Max frame time: {:.2} ms", max_frame_time.as_micros() as f64 / 1000.0))
+    }
+    
+    writeln!(out)?;
+    writeln!(out, "Mean ms per stage (share of the frame)")?;
+    write!(out, "{:<8} {:<8}", "size", "color")?;
+    for stage in Stage::ALL {
+        write!(out, " {:>16}", stage.name())?;
+    }
+    writeln!(out)?;
+    for result in &report.results {
+        write!(out, "{:<8} {:<8}", result.size.name(), result.color_mode.name())?;
+        for stage in &result.stages {
+            write!(out, " {:>16}", format!("{:.3} ({:.0}%)", stage.mean_ms, stage.share * 100.0))?;
+        }
+        writeln!(out)?;
+    }
+    
+    if report.results.iter().any(|result| result.output.is_some()) {
+        writeln!(out)?;
+        writeln!(out, "Output per frame")?;
+        writeln!(
+            out,
+            "{:<8} {:<8} {:>10} {:>10} {:>10} {:>10}",
+            "size", "color", "bytes", "max bytes", "escapes", "cells"
         )?;
-        
-        execute!(
-            stdout,
-            MoveTo(2, 10),
-            SetForegroundColor(Color::Yellow),
-            Print("Press any key to continue to next benchmark...")
+        for result in &report.results {
+            if let Some(output) = &result.output {
+                writeln!(
+                    out,
+                    "{:<8} {:<8} {:>10.0} {:>10} {:>10.1} {:>10.1}",
+                    result.size.name(),
+                    result.color_mode.name(),
+                    output.bytes_per_frame,
+                    output.max_bytes,
+                    output.escapes_per_frame,
+                    output.cells_changed_per_frame
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_csv<W: Write>(report: &BenchmarkReport, out: &mut W) -> Result<()> {
+    let stage_columns = Stage::ALL.map(|stage| format!("{}_ms", stage));
+    writeln!(
+        out,
+        "{},{},{}",
+        COLUMNS.join(","),
+        stage_columns.join(","),
+        OUTPUT_COLUMNS.join(",")
+    )?;
+    for result in &report.results {
+        let stats = &result.stats;
+        write!(
+            out,
+            "{},{},{},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6},{:.3}",
+            result.size.name(),
+            result.color_mode.name(),
+            stats.frames,
+            stats.mean_ms,
+            stats.stddev_ms,
+            stats.min_ms,
+            stats.p50_ms,
+            stats.p95_ms,
+            stats.p99_ms,
+            stats.max_ms,
+            stats.fps
         )?;
-        
-        // Wait for user input before continuing
-        stdout.flush()?;
-        crossterm::event::read()?;
+        for stage in &result.stages {
+            write!(out, ",{:.6}", stage.mean_ms)?;
+        }
+        // Left empty when the target didn't encode anything
+        match &result.output {
+            Some(output) => writeln!(
+                out,
+                ",{:.1},{},{:.1},{:.1}",
+                output.bytes_per_frame, output.max_bytes, output.escapes_per_frame, output.cells_changed_per_frame
+            )?,
+            None => writeln!(out, "{}", ",".repeat(OUTPUT_COLUMNS.len()))?,
+        }
     }
-    
     Ok(())
 }
 
-/// Benchmark a single frame render
-pub fn benchmark_frame(
-    term: &mut Terminal,
+/// Benchmark a single frame render, failing if the target can't take the frame
+pub fn benchmark_frame<T: RenderTarget + ?Sized>(
+    target: &mut T,
     renderer: &mut Renderer,
     waffle: &mut Waffle,
-) -> Duration {
+    ctx: &FrameContext,
+) -> Result<Duration> {
     let start = Instant::now();
-    let _ = renderer.render(term, waffle);
-    start.elapsed()
+    renderer.render(target, waffle, ctx)?;
+    Ok(start.elapsed())
 }
@@ -0,0 +1,155 @@
+use crossterm::style::Color;
+use std::fmt;
+
+use crate::{
+    color,
+    framebuffer::{Cell, FrameBuffer},
+};
+
+/// Characters used for glow, from faint to strong
+const GLOW_CHARS: [char; 3] = ['.', ':', '+'];
+
+/// Glow weaker than this is not drawn at all
+const MIN_GLOW: f64 = 0.08;
+
+/// Terminal-space bloom that spreads light from bright cells into the empty cells around them
+#[derive(Clone)]
+pub struct Bloom {
+    pub enabled: bool,
+    /// How many rows the glow reaches; it reaches twice as many columns since cells are tall
+    pub radius: u16,
+    /// Strength of the glow relative to the source cell
+    pub intensity: f64,
+    /// Minimum brightness (0 to 1) for a cell to glow
+    pub threshold: f64,
+    // Accumulated glow strength and color for every cell, reused between frames
+    glow: Vec<(f64, f64, f64, f64)>,
+}
+
+impl Bloom {
+    pub fn new(radius: u16, intensity: f64) -> Self {
+        Self {
+            enabled: true,
+            radius,
+            intensity,
+            threshold: 0.55,
+            glow: Vec::new(),
+        }
+    }
+    
+    /// A bloom pass that leaves frames untouched
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::default()
+        }
+    }
+    
+    /// Blur bright cells into neighbouring empty cells with a distance falloff
+    pub fn apply(&mut self, frame: &mut FrameBuffer) {
+        if !self.enabled || self.radius == 0 || self.intensity <= 0.0 {
+            return;
+        }
+        
+        let (width, height) = (frame.width() as i32, frame.height() as i32);
+        let radius = self.radius as i32;
+        let reach = radius as f64 + 1.0;
+        
+        // Only grows the buffer when the frame does
+        let glow = &mut self.glow;
+        glow.clear();
+        glow.resize((width * height) as usize, (0.0, 0.0, 0.0, 0.0));
+        
+        for (y, row) in frame.rows().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if cell.is_empty() {
+                    continue;
+                }
+                
+                let brightness = color::luminance(cell.color);
+                if brightness < self.threshold {
+                    continue;
+                }
+                
+                let (r, g, b) = color::to_rgb(cell.color);
+                let (x, y) = (x as i32, y as i32);
+                
+                for dy in -radius..=radius {
+                    for dx in -2 * radius..=2 * radius {
+                        let (nx, ny) = (x + dx, y + dy);
+                        if (dx == 0 && dy == 0) || nx < 0 || ny < 0 || nx >= width || ny >= height {
+                            continue;
+                        }
+                        
+                        // Halve horizontal distance so the glow looks round on tall cells
+                        let distance = ((dx as f64 / 2.0).powi(2) + (dy as f64).powi(2)).sqrt();
+                        if distance >= reach {
+                            continue;
+                        }
+                        
+                        let weight = brightness * (1.0 - distance / reach).powi(2);
+                        let entry = &mut glow[(ny * width + nx) as usize];
+                        entry.0 += weight;
+                        entry.1 += weight * r as f64;
+                        entry.2 += weight * g as f64;
+                        entry.3 += weight * b as f64;
+                    }
+                }
+            }
+        }
+        
+        for y in 0..height {
+            for x in 0..width {
+                let (weight, r, g, b) = glow[(y * width + x) as usize];
+                let strength = (weight * self.intensity).min(1.0);
+                if strength < MIN_GLOW || !frame.get(x as u16, y as u16).is_some_and(Cell::is_empty) {
+                    continue;
+                }
+                
+                // Average color of the contributing cells, dimmed by the glow strength
+                let scale = strength / weight;
+                let index = ((strength * GLOW_CHARS.len() as f64) as usize).min(GLOW_CHARS.len() - 1);
+                frame.set(
+                    x as u16,
+                    y as u16,
+                    Cell::new(
+                        GLOW_CHARS[index],
+                        Color::Rgb {
+                            r: (r * scale) as u8,
+                            g: (g * scale) as u8,
+                            b: (b * scale) as u8,
+                        },
+                        f64::NEG_INFINITY,
+                    ),
+                );
+            }
+        }
+    }
+}
+
+impl PartialEq for Bloom {
+    fn eq(&self, other: &Self) -> bool {
+        // The scratch buffer is no part of the settings
+        self.enabled == other.enabled
+            && self.radius == other.radius
+            && self.intensity == other.intensity
+            && self.threshold == other.threshold
+    }
+}
+
+impl fmt::Debug for Bloom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bloom")
+            .field("enabled", &self.enabled)
+            .field("radius", &self.radius)
+            .field("intensity", &self.intensity)
+            .field("threshold", &self.threshold)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self::new(2, 0.5)
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{raster::ScreenPoint, waffle::Point3D};
+
+/// Typical height-to-width ratio of a terminal character cell
+pub const DEFAULT_CELL_ASPECT: f64 = 2.0;
+
+/// How far inside the near and far planes clipped geometry is cut, so it still projects
+const CLIP_MARGIN: f64 = 1e-9;
+
+/// How the camera maps 3D space onto the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+    /// Distant objects appear smaller
+    Perspective,
+    /// Objects keep their size regardless of distance
+    Orthographic,
+}
+
+/// A viewer looking at the scene
+///
+/// The screen shows the camera's right direction to the right and its up
+/// direction upwards, with the look-at target in the center.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Camera {
+    pub position: Point3D,
+    pub target: Point3D,
+    pub up: Point3D,
+    /// Vertical field of view in radians (perspective only)
+    pub fov: f64,
+    /// Nothing closer than this distance along the view direction is drawn
+    pub near: f64,
+    /// Nothing further than this distance along the view direction is drawn
+    pub far: f64,
+    pub projection: Projection,
+    /// Height of the visible area in world units (orthographic only)
+    pub ortho_height: f64,
+    /// Height of a character cell divided by its width
+    pub cell_aspect: f64,
+}
+
+impl Camera {
+    /// Create a perspective camera at `position` looking at `target`
+    pub fn new(position: Point3D, target: Point3D) -> Self {
+        Self {
+            position,
+            target,
+            up: Point3D::new(0.0, 1.0, 0.0),
+            fov: 50f64.to_radians(),
+            near: 0.1,
+            far: 100.0,
+            projection: Projection::Perspective,
+            ortho_height: 14.0,
+            cell_aspect: DEFAULT_CELL_ASPECT,
+        }
+    }
+    
+    /// Get the orthonormal (right, up, forward) basis of the view
+    pub fn basis(&self) -> (Point3D, Point3D, Point3D) {
+        let forward = (self.target - self.position).normalized();
+        let mut right = forward.cross(self.up).normalized();
+        if right.length() == 0.0 {
+            // Looking straight along the up vector; pick any perpendicular
+            right = forward.cross(Point3D::new(0.0, 0.0, 1.0)).normalized();
+            if right.length() == 0.0 {
+                right = Point3D::new(1.0, 0.0, 0.0);
+            }
+        }
+        let up = right.cross(forward);
+        (right, up, forward)
+    }
+    
+    /// Distance from the camera to the look-at target
+    pub fn target_distance(&self) -> f64 {
+        (self.target - self.position).length()
+    }
+    
+    /// Distance of a point in front of the camera, along the view direction
+    pub fn view_depth(&self, point: Point3D) -> f64 {
+        (point - self.position).dot(self.forward())
+    }
+    
+    /// Direction the camera is looking in
+    pub fn forward(&self) -> Point3D {
+        self.basis().2
+    }
+    
+    /// Move the camera along its view direction so a sphere around the target fits on screen
+    ///
+    /// `margin` is the fraction of the screen's half-extent to keep free around the
+    /// sphere. Orthographic cameras zoom instead of moving.
+    pub fn fit_sphere(&mut self, radius: f64, margin: f64, width: f64, height: f64) {
+        if radius <= 0.0 || width <= 0.0 || height <= 0.0 {
+            return;
+        }
+        
+        // Half-extent of the screen in normalized device coordinates along the tighter axis
+        let horizontal = width / (height * self.cell_aspect);
+        let limit = horizontal.min(1.0) * (1.0 - margin.clamp(0.0, 0.95));
+        
+        match self.projection {
+            Projection::Perspective => {
+                // The sphere's silhouette subtends asin(r / d) from the camera
+                let half_angle = (limit * (self.fov / 2.0).tan()).atan();
+                let distance = radius / half_angle.sin();
+                self.position = self.target - self.forward() * distance;
+                self.far = self.far.max(distance + radius * 2.0);
+            }
+            Projection::Orthographic => {
+                self.ortho_height = 2.0 * radius / limit;
+            }
+        }
+    }
+    
+    /// Zoom in by `factor` (values below 1 zoom out)
+    ///
+    /// Perspective cameras move towards the target; orthographic cameras shrink
+    /// the visible area.
+    pub fn zoom(&mut self, factor: f64) {
+        if factor <= 0.0 || !factor.is_finite() {
+            return;
+        }
+        
+        match self.projection {
+            Projection::Perspective => {
+                let distance = (self.target_distance() / factor).max(self.near * 2.0);
+                self.position = self.target - self.forward() * distance;
+                self.far = self.far.max(distance * 2.0);
+            }
+            Projection::Orthographic => {
+                self.ortho_height /= factor;
+            }
+        }
+    }
+    
+    /// Check whether a surface with the given normal faces the camera
+    pub fn is_facing(&self, point: Point3D, normal: Point3D) -> bool {
+        match self.projection {
+            Projection::Perspective => normal.dot(self.position - point) > 0.0,
+            Projection::Orthographic => normal.dot(self.forward()) < 0.0,
+        }
+    }
+    
+    /// Cut a line down to the part between the near and far planes
+    ///
+    /// Returns the fractions of the way from `a` to `b` where the visible part
+    /// starts and ends, or `None` if none of it is.
+    pub fn clip_line(&self, a: Point3D, b: Point3D) -> Option<(f64, f64)> {
+        let (near, far) = self.clip_range();
+        let (depth_a, depth_b) = (self.view_depth(a), self.view_depth(b));
+        let (mut start, mut end) = (0.0, 1.0);
+        
+        for (limit, inside) in [(near, 1.0), (far, -1.0)] {
+            // Signed distance inside the plane at each end
+            let (from, to) = ((depth_a - limit) * inside, (depth_b - limit) * inside);
+            match (from >= 0.0, to >= 0.0) {
+                (true, true) => {},
+                (false, false) => return None,
+                (false, true) => start = f64::max(start, from / (from - to)),
+                (true, false) => end = f64::min(end, from / (from - to)),
+            }
+        }
+        
+        (start <= end).then_some((start, end))
+    }
+    
+    /// Cut a convex polygon down to the part between the near and far planes
+    ///
+    /// The corners of the visible part are pushed onto `clipped` in order; it is
+    /// left empty if none of the polygon is visible.
+    pub fn clip_polygon(&self, corners: &[Point3D], clipped: &mut Vec<Point3D>) {
+        let (near, far) = self.clip_range();
+        clipped.clear();
+        clipped.extend_from_slice(corners);
+        
+        // Sutherland-Hodgman, one plane at a time
+        let mut input = Vec::with_capacity(corners.len() + 2);
+        for (limit, inside) in [(near, 1.0), (far, -1.0)] {
+            std::mem::swap(&mut input, clipped);
+            clipped.clear();
+            
+            for (i, &current) in input.iter().enumerate() {
+                let previous = input[(i + input.len() - 1) % input.len()];
+                let from = (self.view_depth(previous) - limit) * inside;
+                let to = (self.view_depth(current) - limit) * inside;
+                
+                if (from >= 0.0) != (to >= 0.0) {
+                    clipped.push(previous + (current - previous) * (from / (from - to)));
+                }
+                if to >= 0.0 {
+                    clipped.push(current);
+                }
+            }
+        }
+    }
+    
+    /// Get the view depths clipped geometry is cut to, just inside the near and far planes
+    fn clip_range(&self) -> (f64, f64) {
+        let margin = CLIP_MARGIN * self.far.abs().max(1.0);
+        (self.near + margin, self.far - margin)
+    }
+    
+    /// Project a world-space point onto a screen of the given size in cells
+    ///
+    /// The returned depth is relative to the target: positive values are in front
+    /// of it and larger values are closer. Points outside the near and far planes
+    /// return `None`.
+    pub fn project(&self, point: Point3D, width: f64, height: f64) -> Option<ScreenPoint> {
+        let (right, up, forward) = self.basis();
+        let offset = point - self.position;
+        let (view_x, view_y, view_z) = (offset.dot(right), offset.dot(up), offset.dot(forward));
+        
+        if view_z < self.near || view_z > self.far {
+            return None;
+        }
+        
+        // Normalized device coordinates: -1 to 1 spans the visible height
+        let (ndc_x, ndc_y, inv_w) = match self.projection {
+            Projection::Perspective => {
+                let half_height = view_z * (self.fov / 2.0).tan();
+                (view_x / half_height, view_y / half_height, 1.0 / view_z)
+            }
+            Projection::Orthographic => {
+                let half_height = self.ortho_height / 2.0;
+                (view_x / half_height, view_y / half_height, 1.0)
+            }
+        };
+        
+        // Cells are taller than wide, so the same distance needs more columns than rows
+        let scale = height / 2.0;
+        Some(ScreenPoint::with_inv_w(
+            width / 2.0 + ndc_x * scale * self.cell_aspect,
+            height / 2.0 - ndc_y * scale,
+            self.target_distance() - view_z,
+            inv_w,
+        ))
+    }
+}
+
+impl Default for Camera {
+    /// A perspective camera in front of the waffle's top surface
+    fn default() -> Self {
+        Self::new(Point3D::new(0.0, 0.0, 16.0), Point3D::new(0.0, 0.0, 0.0))
+    }
+}
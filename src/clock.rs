@@ -0,0 +1,88 @@
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// Source of time that drives animations
+pub trait Clock {
+    /// Advance the clock and return the time elapsed since the previous tick
+    fn tick(&mut self) -> Duration;
+}
+
+/// Wall-clock time
+///
+/// The first tick returns zero so an animation starts from its initial state.
+#[derive(Debug, Default)]
+pub struct RealClock {
+    last_tick: Option<Instant>,
+}
+
+impl RealClock {
+    pub fn new() -> Self {
+        Self { last_tick: None }
+    }
+}
+
+impl Clock for RealClock {
+    fn tick(&mut self) -> Duration {
+        let now = Instant::now();
+        let dt = self.last_tick.map_or(Duration::ZERO, |last| now.duration_since(last));
+        self.last_tick = Some(now);
+        dt
+    }
+}
+
+/// Clock that advances by the same step on every tick, regardless of real time
+#[derive(Debug, Clone, Copy)]
+pub struct FixedStepClock {
+    step: Duration,
+}
+
+impl FixedStepClock {
+    pub fn new(step: Duration) -> Self {
+        Self { step }
+    }
+    
+    /// Create a clock that steps by one frame at the given frame rate
+    pub fn from_fps(fps: u32) -> Self {
+        Self::new(Duration::from_secs(1) / fps.max(1))
+    }
+    
+    /// Get the step per tick
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+}
+
+impl Clock for FixedStepClock {
+    fn tick(&mut self) -> Duration {
+        self.step
+    }
+}
+
+/// Clock that only moves when told to
+///
+/// Clones share the same time, so a test can keep a handle while an
+/// `Animation` owns another.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    pending: Rc<Cell<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Move time forward; the next tick returns everything advanced since the last one
+    pub fn advance(&self, dt: Duration) {
+        self.pending.set(self.pending.get() + dt);
+    }
+}
+
+impl Clock for ManualClock {
+    fn tick(&mut self) -> Duration {
+        self.pending.replace(Duration::ZERO)
+    }
+}
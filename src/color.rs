@@ -0,0 +1,175 @@
+use crossterm::style::Color;
+
+/// The 16 basic terminal colors in palette order
+const BASIC_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::DarkRed,
+    Color::DarkGreen,
+    Color::DarkYellow,
+    Color::DarkBlue,
+    Color::DarkMagenta,
+    Color::DarkCyan,
+    Color::Grey,
+    Color::DarkGrey,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+];
+
+/// Channel values of the 6x6x6 color cube in the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// How many colors the output may use
+///
+/// Fewer colors mean shorter escape sequences and more cells sharing a color,
+/// so less has to be written to the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    /// The 16 basic colors
+    Ansi16,
+    /// The 256-color palette
+    Ansi256,
+    /// 24-bit RGB
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Guess what a terminal supports from its `COLORTERM` and `TERM` variables
+    pub fn detect(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        let colorterm = colorterm.unwrap_or_default().to_ascii_lowercase();
+        let term = term.unwrap_or_default().to_ascii_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" || term.contains("direct") {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+    
+    /// Get a name for reports, e.g. "256 colors"
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorDepth::Ansi16 => "16 colors",
+            ColorDepth::Ansi256 => "256 colors",
+            ColorDepth::TrueColor => "24-bit color",
+        }
+    }
+    
+    /// Get the closest color that can be shown at this depth
+    pub fn quantize(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorDepth::TrueColor, _) => color,
+            (ColorDepth::Ansi256, Color::Rgb { r, g, b }) => Color::AnsiValue(nearest_ansi256(r, g, b)),
+            (ColorDepth::Ansi256, _) => color,
+            (ColorDepth::Ansi16, Color::Rgb { .. } | Color::AnsiValue(_)) => nearest_basic(to_rgb(color)),
+            (ColorDepth::Ansi16, _) => color,
+        }
+    }
+}
+
+/// Find the closest entry of the 256-color palette, from the color cube or the grey ramp
+fn nearest_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - v as i32).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = 16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8;
+    
+    // Greys 8, 18, ..., 238 in 24 steps
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let grey = 232 + ((average.saturating_sub(3)) / 10).min(23) as u8;
+    
+    if distance((r, g, b), ansi_to_rgb(grey)) < distance((r, g, b), ansi_to_rgb(cube)) {
+        grey
+    } else {
+        cube
+    }
+}
+
+/// Find the closest of the 16 basic colors
+fn nearest_basic(rgb: (u8, u8, u8)) -> Color {
+    BASIC_COLORS
+        .iter()
+        .copied()
+        .min_by_key(|&color| distance(rgb, to_rgb(color)))
+        .unwrap_or(Color::White)
+}
+
+/// Squared distance between two RGB colors
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// Convert a terminal color to RGB components
+///
+/// Named colors use the common xterm palette values.
+pub fn to_rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb { r, g, b } => (r, g, b),
+        Color::Black => (0, 0, 0),
+        Color::DarkGrey => (128, 128, 128),
+        Color::Red => (255, 0, 0),
+        Color::DarkRed => (128, 0, 0),
+        Color::Green => (0, 255, 0),
+        Color::DarkGreen => (0, 128, 0),
+        Color::Yellow => (255, 255, 0),
+        Color::DarkYellow => (128, 128, 0),
+        Color::Blue => (0, 0, 255),
+        Color::DarkBlue => (0, 0, 128),
+        Color::Magenta => (255, 0, 255),
+        Color::DarkMagenta => (128, 0, 128),
+        Color::Cyan => (0, 255, 255),
+        Color::DarkCyan => (0, 128, 128),
+        Color::White => (255, 255, 255),
+        Color::Grey => (192, 192, 192),
+        Color::AnsiValue(value) => ansi_to_rgb(value),
+        Color::Reset => (255, 255, 255),
+    }
+}
+
+/// Perceived brightness of a color from 0 (black) to 1 (white)
+pub fn luminance(color: Color) -> f64 {
+    let (r, g, b) = to_rgb(color);
+    (0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64) / 255.0
+}
+
+/// Approximate RGB value of an entry in the 256-color palette
+fn ansi_to_rgb(value: u8) -> (u8, u8, u8) {
+    const BASIC: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (128, 0, 0),
+        (0, 128, 0),
+        (128, 128, 0),
+        (0, 0, 128),
+        (128, 0, 128),
+        (0, 128, 128),
+        (192, 192, 192),
+        (128, 128, 128),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (0, 0, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    match value {
+        0..=15 => BASIC[value as usize],
+        16..=231 => {
+            let i = value - 16;
+            (CUBE_LEVELS[(i / 36) as usize], CUBE_LEVELS[(i / 6 % 6) as usize], CUBE_LEVELS[(i % 6) as usize])
+        }
+        _ => {
+            let level = 8 + (value - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
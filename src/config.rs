@@ -0,0 +1,589 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{de, Deserialize, Deserializer};
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    animation::{Animation, AnimationType},
+    app::{MAX_FPS, MAX_SPEED, MIN_FPS, MIN_SPEED},
+    bloom::Bloom,
+    camera::{Projection, DEFAULT_CELL_ASPECT},
+    input::{BindingSpec, KeyMap},
+    lighting::{Light, Lighting},
+    renderer::{ColorMode, Renderer},
+    timestep::DEFAULT_UPDATE_RATE,
+    waffle::{Size, Waffle},
+};
+
+/// Prefix of the environment variables that override config file settings
+pub const ENV_PREFIX: &str = "GLOWING_WAFFLE_";
+
+/// Environment variable naming a config file to use instead of the default one
+pub const CONFIG_ENV: &str = "GLOWING_WAFFLE_CONFIG";
+
+/// Separator between the entries of list settings in environment variables
+const ENV_LIST_SEPARATOR: char = ';';
+
+/// Narrowest vertical field of view in degrees
+pub const MIN_FOV: f64 = 1.0;
+
+/// Widest vertical field of view in degrees
+pub const MAX_FOV: f64 = 179.0;
+
+/// Widest glow in rows; the cost of bloom grows with the square of the radius
+pub const MAX_BLOOM_RADIUS: u16 = 16;
+
+/// Shading characters from sparse to dense
+///
+/// An alias rather than a plain `Vec<char>` so clap treats a palette as one value
+/// instead of a list of characters.
+pub type Palette = Vec<char>;
+
+/// One layer of settings, e.g. from a config file, the environment or the command line
+///
+/// Every field is optional; unset fields leave the layer below untouched. Keys
+/// are named after the command line flags, with switches like `--no-bloom`
+/// written as `bloom = false`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    #[serde(deserialize_with = "parsed")]
+    pub size: Option<Size>,
+    #[serde(deserialize_with = "parsed")]
+    pub color: Option<ColorMode>,
+    /// Shading characters from sparse to dense
+    #[serde(deserialize_with = "palette")]
+    pub palette: Option<Palette>,
+    #[serde(deserialize_with = "parsed")]
+    pub animation: Option<AnimationType>,
+    /// Rotation speed multiplier
+    #[serde(deserialize_with = "speed")]
+    pub speed: Option<f64>,
+    #[serde(deserialize_with = "fps")]
+    pub fps: Option<u32>,
+    pub update_rate: Option<u32>,
+    /// Light sources replacing the default light
+    #[serde(deserialize_with = "parsed_list")]
+    pub lights: Option<Vec<Light>>,
+    #[serde(deserialize_with = "ambient")]
+    pub ambient: Option<f64>,
+    pub bloom: Option<bool>,
+    #[serde(deserialize_with = "bloom_radius")]
+    pub bloom_radius: Option<u16>,
+    #[serde(deserialize_with = "non_negative")]
+    pub bloom_intensity: Option<f64>,
+    #[serde(deserialize_with = "fov")]
+    pub fov: Option<f64>,
+    pub orthographic: Option<bool>,
+    #[serde(deserialize_with = "positive")]
+    pub cell_aspect: Option<f64>,
+    pub auto_fit: Option<bool>,
+    /// Key bindings applied on top of those from lower layers
+    #[serde(deserialize_with = "parsed_list")]
+    pub bindings: Option<Vec<BindingSpec>>,
+    pub mouse: Option<bool>,
+    pub stats: Option<bool>,
+    pub profile: Option<bool>,
+    pub adaptive: Option<bool>,
+}
+
+impl Config {
+    /// Every setting key, as accepted by [`Config::set`] and in config files
+    pub const KEYS: [&'static str; 21] = [
+        "size",
+        "color",
+        "palette",
+        "animation",
+        "speed",
+        "fps",
+        "update-rate",
+        "lights",
+        "ambient",
+        "bloom",
+        "bloom-radius",
+        "bloom-intensity",
+        "fov",
+        "orthographic",
+        "cell-aspect",
+        "auto-fit",
+        "bindings",
+        "mouse",
+        "stats",
+        "profile",
+        "adaptive",
+    ];
+    
+    /// Parse a config file's contents
+    pub fn parse(text: &str) -> Result<Self> {
+        // TOML errors already show the line and the offending key
+        toml::from_str(text).map_err(|err| anyhow!("{}", err.to_string().trim_end()))
+    }
+    
+    /// Read a config file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read config file {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+    
+    /// Collect settings from `GLOWING_WAFFLE_*` variables, e.g. `GLOWING_WAFFLE_BLOOM_RADIUS=3`
+    ///
+    /// List settings separate their entries with `;`. Variables that don't name a
+    /// setting are skipped with a warning, as they may be meant for another version.
+    pub fn from_vars<I, K, V>(vars: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut config = Self::default();
+        for (name, value) in vars {
+            let name = name.as_ref();
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            if name == CONFIG_ENV {
+                continue;
+            }
+            
+            let key = key.to_ascii_lowercase().replace('_', "-");
+            if !Self::KEYS.contains(&key.as_str()) {
+                eprintln!("Ignoring {}: there is no '{}' setting", name, key);
+                continue;
+            }
+            config
+                .set(&key, value.as_ref())
+                .with_context(|| format!("Invalid environment variable {}", name))?;
+        }
+        Ok(config)
+    }
+    
+    /// Collect settings from the environment of this process
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(env::vars())
+    }
+    
+    /// Set one setting from text, using the same key names as the config file
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "size" => self.size = Some(value.parse()?),
+            "color" => self.color = Some(value.parse()?),
+            "palette" => self.palette = Some(parse_palette(value)?),
+            "animation" => self.animation = Some(value.parse()?),
+            "speed" => self.speed = Some(parse_speed(value)?),
+            "fps" => self.fps = Some(parse_fps(value)?),
+            "update-rate" => self.update_rate = Some(parse_number(value)?),
+            "lights" => self.lights = Some(parse_list(value)?),
+            "ambient" => self.ambient = Some(parse_ambient(value)?),
+            "bloom" => self.bloom = Some(parse_bool(value)?),
+            "bloom-radius" => self.bloom_radius = Some(parse_bloom_radius(value)?),
+            "bloom-intensity" => self.bloom_intensity = Some(parse_non_negative(value)?),
+            "fov" => self.fov = Some(parse_fov(value)?),
+            "orthographic" => self.orthographic = Some(parse_bool(value)?),
+            "cell-aspect" => self.cell_aspect = Some(parse_positive(value)?),
+            "auto-fit" => self.auto_fit = Some(parse_bool(value)?),
+            "bindings" => self.bindings = Some(parse_list(value)?),
+            "mouse" => self.mouse = Some(parse_bool(value)?),
+            "stats" => self.stats = Some(parse_bool(value)?),
+            "profile" => self.profile = Some(parse_bool(value)?),
+            "adaptive" => self.adaptive = Some(parse_bool(value)?),
+            _ => bail!("Unknown setting '{}'", key),
+        }
+        Ok(())
+    }
+}
+
+/// Find the config file: `$XDG_CONFIG_HOME/glowing-waffle/config.toml`, or under `~/.config`
+pub fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("glowing-waffle").join("config.toml"))
+}
+
+/// Fully resolved settings for a run of the visualizer
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub size: Size,
+    pub color: ColorMode,
+    /// Shading characters, or `None` for the renderer's own
+    pub palette: Option<Palette>,
+    pub animation: AnimationType,
+    pub speed: f64,
+    pub fps: u32,
+    pub update_rate: u32,
+    /// Light sources, or empty for the default light
+    pub lights: Vec<Light>,
+    pub ambient: Option<f64>,
+    pub bloom: bool,
+    pub bloom_radius: u16,
+    pub bloom_intensity: f64,
+    /// Vertical field of view in degrees
+    pub fov: f64,
+    pub orthographic: bool,
+    pub cell_aspect: f64,
+    pub auto_fit: bool,
+    pub bindings: Vec<BindingSpec>,
+    pub mouse: bool,
+    pub stats: bool,
+    pub profile: bool,
+    pub adaptive: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let bloom = Bloom::default();
+        Self {
+            size: Size::Medium,
+            color: ColorMode::Normal,
+            palette: None,
+            animation: AnimationType::Rotate,
+            speed: 1.0,
+            fps: 30,
+            update_rate: DEFAULT_UPDATE_RATE,
+            lights: Vec::new(),
+            ambient: None,
+            bloom: true,
+            bloom_radius: bloom.radius,
+            bloom_intensity: bloom.intensity,
+            fov: 50.0,
+            orthographic: false,
+            cell_aspect: DEFAULT_CELL_ASPECT,
+            auto_fit: true,
+            bindings: Vec::new(),
+            mouse: true,
+            stats: false,
+            profile: false,
+            adaptive: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Resolve settings from the defaults, the config file, the environment and the command line
+    ///
+    /// The config file is `config_path` if given, otherwise the one named by
+    /// `GLOWING_WAFFLE_CONFIG`, otherwise the default location if it exists.
+    pub fn load(config_path: Option<&Path>, cli: &Config) -> Result<Self> {
+        let explicit = config_path.map(Path::to_path_buf).or_else(|| env::var_os(CONFIG_ENV).map(PathBuf::from));
+        let file = match explicit {
+            Some(path) => Config::from_file(&path)?,
+            None => match default_path() {
+                Some(path) if path.exists() => Config::from_file(&path)?,
+                _ => Config::default(),
+            },
+        };
+        
+        Ok(Self::from_layers([&file, &Config::from_env()?, cli]))
+    }
+    
+    /// Apply layers of settings on top of the defaults, later layers winning
+    pub fn from_layers<'a>(layers: impl IntoIterator<Item = &'a Config>) -> Self {
+        let mut settings = Self::default();
+        for layer in layers {
+            settings.apply(layer);
+        }
+        settings
+    }
+    
+    /// Override these settings with everything set in a layer
+    pub fn apply(&mut self, layer: &Config) {
+        fn set<T: Clone>(value: &mut T, layer: &Option<T>) {
+            if let Some(layer) = layer {
+                value.clone_from(layer);
+            }
+        }
+        
+        set(&mut self.size, &layer.size);
+        set(&mut self.color, &layer.color);
+        if layer.palette.is_some() {
+            self.palette.clone_from(&layer.palette);
+        }
+        set(&mut self.animation, &layer.animation);
+        set(&mut self.speed, &layer.speed);
+        set(&mut self.fps, &layer.fps);
+        set(&mut self.update_rate, &layer.update_rate);
+        set(&mut self.lights, &layer.lights);
+        if layer.ambient.is_some() {
+            self.ambient = layer.ambient;
+        }
+        set(&mut self.bloom, &layer.bloom);
+        set(&mut self.bloom_radius, &layer.bloom_radius);
+        set(&mut self.bloom_intensity, &layer.bloom_intensity);
+        set(&mut self.fov, &layer.fov);
+        set(&mut self.orthographic, &layer.orthographic);
+        set(&mut self.cell_aspect, &layer.cell_aspect);
+        set(&mut self.auto_fit, &layer.auto_fit);
+        // Bindings stack, so a layer can rebind a single key without repeating the rest
+        if let Some(bindings) = &layer.bindings {
+            self.bindings.extend(bindings.iter().copied());
+        }
+        set(&mut self.mouse, &layer.mouse);
+        set(&mut self.stats, &layer.stats);
+        set(&mut self.profile, &layer.profile);
+        set(&mut self.adaptive, &layer.adaptive);
+    }
+    
+    /// Create the waffle model
+    pub fn waffle(&self) -> Waffle {
+        Waffle::new(self.size)
+    }
+    
+    /// Create a renderer with the color, lighting, glow and camera settings
+    pub fn renderer(&self) -> Renderer {
+        let mut renderer = Renderer::new(self.color);
+        if let Some(palette) = &self.palette {
+            renderer.set_char_palette(palette.clone());
+        }
+        
+        // Start from the default key light
+        let mut lighting = Lighting::default();
+        if !self.lights.is_empty() {
+            lighting.set_lights(self.lights.clone());
+        }
+        if let Some(ambient) = self.ambient {
+            lighting.ambient = ambient;
+        }
+        renderer.set_lighting(lighting);
+        
+        let mut bloom = Bloom::new(self.bloom_radius, self.bloom_intensity);
+        bloom.enabled = self.bloom;
+        renderer.set_bloom(bloom);
+        
+        renderer.set_cell_aspect(self.cell_aspect);
+        renderer.set_auto_fit(self.auto_fit);
+        let camera = renderer.camera_mut();
+        camera.fov = self.fov.to_radians();
+        if self.orthographic {
+            camera.projection = Projection::Orthographic;
+        }
+        
+        renderer
+    }
+    
+    /// Create the animation
+    pub fn animation(&self) -> Animation {
+        let mut animation = Animation::new();
+        animation.set_animation_type(self.animation);
+        animation.set_speed(self.speed);
+        animation
+    }
+    
+    /// Create the key bindings, applying the configured ones on top of the defaults
+    pub fn keymap(&self) -> KeyMap {
+        let mut keymap = KeyMap::default();
+        for binding in &self.bindings {
+            keymap.apply(*binding);
+        }
+        keymap
+    }
+}
+
+/// Parse a number that must be positive and finite, such as a cell aspect ratio
+pub fn parse_positive(value: &str) -> Result<f64> {
+    check_positive(parse_number(value)?)
+}
+
+/// Parse a number that must be zero or more and finite, such as a glow strength
+pub fn parse_non_negative(value: &str) -> Result<f64> {
+    check_non_negative(parse_number(value)?)
+}
+
+/// Parse an ambient light level from 0 to 1
+pub fn parse_ambient(value: &str) -> Result<f64> {
+    check_ambient(parse_number(value)?)
+}
+
+/// Parse a vertical field of view in degrees
+pub fn parse_fov(value: &str) -> Result<f64> {
+    check_fov(parse_number(value)?)
+}
+
+/// Parse how many rows the glow spreads
+pub fn parse_bloom_radius(value: &str) -> Result<u16> {
+    check_bloom_radius(parse_number(value)?)
+}
+
+/// Parse a frame rate, which must be within what the frame rate keys can reach
+pub fn parse_fps(value: &str) -> Result<u32> {
+    check_fps(parse_number(value)?)
+}
+
+/// Parse a rotation speed multiplier
+pub fn parse_speed(value: &str) -> Result<f64> {
+    check_speed(parse_number(value)?)
+}
+
+fn check_positive(number: f64) -> Result<f64> {
+    if number <= 0.0 || !number.is_finite() {
+        bail!("Expected a positive number, got {}", number);
+    }
+    Ok(number)
+}
+
+fn check_non_negative(number: f64) -> Result<f64> {
+    if number < 0.0 || !number.is_finite() {
+        bail!("Expected zero or a positive number, got {}", number);
+    }
+    Ok(number)
+}
+
+fn check_ambient(ambient: f64) -> Result<f64> {
+    if !(0.0..=1.0).contains(&ambient) {
+        bail!("Ambient light must be between 0 and 1, got {}", ambient);
+    }
+    Ok(ambient)
+}
+
+fn check_fov(fov: f64) -> Result<f64> {
+    if !(MIN_FOV..=MAX_FOV).contains(&fov) {
+        bail!("Field of view must be between {} and {} degrees, got {}", MIN_FOV, MAX_FOV, fov);
+    }
+    Ok(fov)
+}
+
+fn check_bloom_radius(radius: u16) -> Result<u16> {
+    if radius > MAX_BLOOM_RADIUS {
+        bail!("Bloom radius must be at most {}, got {}", MAX_BLOOM_RADIUS, radius);
+    }
+    Ok(radius)
+}
+
+fn check_fps(fps: u32) -> Result<u32> {
+    if !(MIN_FPS..=MAX_FPS).contains(&fps) {
+        bail!("Frame rate must be between {} and {}, got {}", MIN_FPS, MAX_FPS, fps);
+    }
+    Ok(fps)
+}
+
+fn check_speed(speed: f64) -> Result<f64> {
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        bail!("Speed must be between {} and {}, got {}", MIN_SPEED, MAX_SPEED, speed);
+    }
+    Ok(speed)
+}
+
+/// Parse a shading palette, which needs at least two characters
+pub fn parse_palette(value: &str) -> Result<Palette> {
+    let palette: Palette = value.chars().collect();
+    if palette.len() < 2 {
+        bail!("A palette needs at least two characters, from sparse to dense");
+    }
+    Ok(palette)
+}
+
+fn parse_number<T>(value: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|err| anyhow!("Invalid number '{}': {}", value.trim(), err))
+}
+
+fn parse_bool(value: &str) -> Result<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        other => bail!("Expected true or false, got '{}'", other),
+    }
+}
+
+fn parse_list<T>(value: &str) -> Result<Vec<T>>
+where
+    T: FromStr<Err = anyhow::Error>,
+{
+    value
+        .split(ENV_LIST_SEPARATOR)
+        .filter(|entry| !entry.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Deserialize a string through `FromStr`
+fn parsed<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let text = String::deserialize(deserializer)?;
+    text.parse().map(Some).map_err(de::Error::custom)
+}
+
+/// Deserialize a list of strings through `FromStr`
+fn parsed_list<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let entries = Vec::<String>::deserialize(deserializer)?;
+    entries
+        .iter()
+        .map(|entry| entry.parse().map_err(de::Error::custom))
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+fn palette<'de, D>(deserializer: D) -> Result<Option<Palette>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let text = String::deserialize(deserializer)?;
+    parse_palette(&text).map(Some).map_err(de::Error::custom)
+}
+
+fn fps<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    check_fps(u32::deserialize(deserializer)?).map(Some).map_err(de::Error::custom)
+}
+
+fn speed<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    check_speed(f64::deserialize(deserializer)?).map(Some).map_err(de::Error::custom)
+}
+
+fn positive<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    check_positive(f64::deserialize(deserializer)?).map(Some).map_err(de::Error::custom)
+}
+
+fn non_negative<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    check_non_negative(f64::deserialize(deserializer)?).map(Some).map_err(de::Error::custom)
+}
+
+fn ambient<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    check_ambient(f64::deserialize(deserializer)?).map(Some).map_err(de::Error::custom)
+}
+
+fn fov<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    check_fov(f64::deserialize(deserializer)?).map(Some).map_err(de::Error::custom)
+}
+
+fn bloom_radius<'de, D>(deserializer: D) -> Result<Option<u16>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    check_bloom_radius(u16::deserialize(deserializer)?).map(Some).map_err(de::Error::custom)
+}
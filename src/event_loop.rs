@@ -0,0 +1,130 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyEvent, MouseEvent};
+use std::time::{Duration, Instant};
+
+use crate::framebuffer::RenderTarget;
+
+/// Something for an [`EventHandler`] to react to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppEvent {
+    Key(KeyEvent),
+    Mouse(MouseEvent),
+    /// The target changed size; it has already been resized when this arrives
+    Resize(u16, u16),
+    /// Time to advance and draw a frame
+    Tick,
+    FocusGained,
+    FocusLost,
+}
+
+/// What the event loop should do after an event was handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Quit,
+    /// Change the time between ticks, starting from the last tick
+    SetFrameDuration(Duration),
+}
+
+/// Reacts to events from the event loop
+pub trait EventHandler<T: RenderTarget + ?Sized> {
+    /// Handle one event; `target` is where frames should be drawn on [`AppEvent::Tick`]
+    fn handle_event(&mut self, event: AppEvent, target: &mut T) -> Result<Control>;
+}
+
+impl<T, F> EventHandler<T> for F
+where
+    T: RenderTarget + ?Sized,
+    F: FnMut(AppEvent, &mut T) -> Result<Control>,
+{
+    fn handle_event(&mut self, event: AppEvent, target: &mut T) -> Result<Control> {
+        self(event, target)
+    }
+}
+
+/// Where the event loop gets terminal events from
+pub trait EventSource {
+    /// Wait up to `timeout` for the next event
+    fn next_event(&mut self, timeout: Duration) -> Result<Option<Event>>;
+}
+
+/// Reads events from the terminal through crossterm
+#[derive(Debug, Default)]
+pub struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn next_event(&mut self, timeout: Duration) -> Result<Option<Event>> {
+        if event::poll(timeout)? {
+            Ok(Some(event::read()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Dispatch events to `handler` until it returns [`Control::Quit`]
+///
+/// Between ticks the loop sleeps inside the event source until either an
+/// event arrives or the next tick is due, so an idle loop uses no CPU. Ticks
+/// that were missed because a frame took too long are dropped rather than
+/// delivered in a burst, and input that queued up meanwhile is handled before
+/// the next tick so a slow frame can't starve it.
+pub fn run<T, S, H>(target: &mut T, source: &mut S, handler: &mut H, frame_duration: Duration) -> Result<()>
+where
+    T: RenderTarget + ?Sized,
+    S: EventSource + ?Sized,
+    H: EventHandler<T> + ?Sized,
+{
+    let mut frame_duration = frame_duration;
+    let mut last_tick = Instant::now();
+    let mut next_tick = last_tick;
+    // Whether the source had nothing left to hand out since the last tick
+    let mut drained = true;
+    
+    loop {
+        let now = Instant::now();
+        let event = if now >= next_tick && drained {
+            last_tick = now;
+            next_tick = now + frame_duration;
+            drained = false;
+            Some(AppEvent::Tick)
+        } else {
+            // Once the tick is due this doesn't wait, it only drains pending input
+            match source.next_event(next_tick.saturating_duration_since(now))? {
+                Some(event) => translate(event, target),
+                None => {
+                    drained = true;
+                    None
+                },
+            }
+        };
+        
+        let Some(event) = event else {
+            continue;
+        };
+        
+        match handler.handle_event(event, target)? {
+            Control::Continue => {},
+            Control::Quit => return Ok(()),
+            Control::SetFrameDuration(duration) => {
+                frame_duration = duration;
+                next_tick = last_tick + duration;
+            },
+        }
+    }
+}
+
+/// Turn a terminal event into an app event, resizing the target first if needed
+fn translate<T: RenderTarget + ?Sized>(event: Event, target: &mut T) -> Option<AppEvent> {
+    match event {
+        Event::Key(key) => Some(AppEvent::Key(key)),
+        Event::Mouse(mouse) => Some(AppEvent::Mouse(mouse)),
+        Event::Resize(width, height) => {
+            target.resize(width, height);
+            Some(AppEvent::Resize(width, height))
+        },
+        Event::FocusGained => Some(AppEvent::FocusGained),
+        Event::FocusLost => Some(AppEvent::FocusLost),
+        _ => None,
+    }
+}
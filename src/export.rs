@@ -0,0 +1,376 @@
+use anyhow::Result;
+use clap::{builder::PossibleValue, ValueEnum};
+use crossterm::{
+    queue,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use std::{
+    fmt,
+    io::Write,
+    path::Path,
+    time::Duration,
+};
+
+use crate::{
+    color,
+    framebuffer::{Cell, FrameBuffer, RenderTarget},
+    screen::{OutputStats, ScreenBuffer},
+};
+
+/// Size of one character cell in SVG output, in pixels
+const SVG_CELL: (u32, u32) = (9, 18);
+
+/// Font size of SVG text, in pixels
+const SVG_FONT_SIZE: u32 = 15;
+
+/// Page and SVG background
+const BACKGROUND: &str = "#000000";
+
+/// File format for a single exported frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Plain characters without color
+    Text,
+    /// Characters with ANSI color escape sequences
+    Ansi,
+    /// A colored `<pre>` block in a standalone HTML page
+    Html,
+    /// Colored monospace text in a standalone SVG image
+    Svg,
+}
+
+impl ExportFormat {
+    /// Every export format
+    pub const ALL: [ExportFormat; 4] = [ExportFormat::Text, ExportFormat::Ansi, ExportFormat::Html, ExportFormat::Svg];
+    
+    /// Get the name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "txt",
+            ExportFormat::Ansi => "ansi",
+            ExportFormat::Html => "html",
+            ExportFormat::Svg => "svg",
+        }
+    }
+    
+    /// Get a short description for listings and help
+    pub fn description(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "Plain text without color",
+            ExportFormat::Ansi => "Text with terminal color codes, for cat or less -R",
+            ExportFormat::Html => "Web page with a colored text block",
+            ExportFormat::Svg => "Vector image",
+        }
+    }
+    
+    /// Guess the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "txt" | "text" => Some(ExportFormat::Text),
+            "ans" | "ansi" => Some(ExportFormat::Ansi),
+            "html" | "htm" => Some(ExportFormat::Html),
+            "svg" => Some(ExportFormat::Svg),
+            _ => None,
+        }
+    }
+}
+
+impl ValueEnum for ExportFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &ExportFormat::ALL
+    }
+    
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()).help(self.description()))
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Write a frame in the given format
+pub fn write_frame<W: Write>(frame: &FrameBuffer, format: ExportFormat, out: &mut W) -> Result<()> {
+    match format {
+        ExportFormat::Text => out.write_all(frame.to_text().as_bytes())?,
+        ExportFormat::Ansi => write_ansi(frame, out)?,
+        ExportFormat::Html => write_html(frame, out)?,
+        ExportFormat::Svg => write_svg(frame, out)?,
+    }
+    Ok(())
+}
+
+fn write_ansi<W: Write>(frame: &FrameBuffer, out: &mut W) -> Result<()> {
+    for row in frame.rows() {
+        let mut color = None;
+        for cell in row {
+            if !cell.is_empty() && color != Some(cell.color) {
+                queue!(out, SetForegroundColor(cell.color))?;
+                color = Some(cell.color);
+            }
+            queue!(out, Print(cell.ch))?;
+        }
+        // Reset at the end of every line so the file can be viewed a page at a time
+        queue!(out, ResetColor, Print('\n'))?;
+    }
+    Ok(())
+}
+
+fn write_html<W: Write>(frame: &FrameBuffer, out: &mut W) -> Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Glowing Waffle</title>")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body style=\"background: {}; margin: 0\">", BACKGROUND)?;
+    write!(out, "<pre style=\"font-family: monospace; line-height: 1.1; margin: 1em\">")?;
+    
+    for row in frame.rows() {
+        for (color, text) in color_runs(row) {
+            match color {
+                Some(color) => write!(out, "<span style=\"color: {}\">{}</span>", hex(color), escape_xml(&text))?,
+                None => write!(out, "{}", text)?,
+            }
+        }
+        writeln!(out)?;
+    }
+    
+    writeln!(out, "</pre>")?;
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")?;
+    Ok(())
+}
+
+fn write_svg<W: Write>(frame: &FrameBuffer, out: &mut W) -> Result<()> {
+    let (cell_width, cell_height) = SVG_CELL;
+    let width = frame.width() as u32 * cell_width;
+    let height = frame.height() as u32 * cell_height;
+    
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+        width, height
+    )?;
+    writeln!(out, "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>", BACKGROUND)?;
+    writeln!(
+        out,
+        "<g font-family=\"monospace\" font-size=\"{}\" xml:space=\"preserve\">",
+        SVG_FONT_SIZE
+    )?;
+    
+    for (y, row) in frame.rows().enumerate() {
+        // Place every run at its column so a font of a slightly different width can't drift
+        let baseline = y as u32 * cell_height + cell_height * 3 / 4;
+        let mut x = 0;
+        for (color, text) in color_runs(row) {
+            let len = text.chars().count() as u32;
+            if let Some(color) = color {
+                writeln!(
+                    out,
+                    "<text x=\"{}\" y=\"{}\" textLength=\"{}\" fill=\"{}\">{}</text>",
+                    x * cell_width,
+                    baseline,
+                    len * cell_width,
+                    hex(color),
+                    escape_xml(&text)
+                )?;
+            }
+            x += len;
+        }
+    }
+    
+    writeln!(out, "</g>")?;
+    writeln!(out, "</svg>")?;
+    Ok(())
+}
+
+/// Split a row into runs of the same color; blank runs have no color
+fn color_runs(row: &[Cell]) -> Vec<(Option<Color>, String)> {
+    let mut runs: Vec<(Option<Color>, String)> = Vec::new();
+    for cell in row {
+        let color = (!cell.is_empty()).then_some(cell.color);
+        match runs.last_mut() {
+            Some((last, text)) if *last == color => text.push(cell.ch),
+            _ => runs.push((color, cell.ch.to_string())),
+        }
+    }
+    runs
+}
+
+fn hex(color: Color) -> String {
+    let (r, g, b) = color::to_rgb(color);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// File format for a recorded animation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// The escape sequences a terminal would receive, played back with `cat`
+    Ansi,
+    /// An asciicast v2 recording with timestamps, played back with `asciinema play`
+    Cast,
+}
+
+impl RecordFormat {
+    /// Every record format
+    pub const ALL: [RecordFormat; 2] = [RecordFormat::Ansi, RecordFormat::Cast];
+    
+    /// Get the name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            RecordFormat::Ansi => "ansi",
+            RecordFormat::Cast => "cast",
+        }
+    }
+    
+    /// Get a short description for listings and help
+    pub fn description(self) -> &'static str {
+        match self {
+            RecordFormat::Ansi => "Raw terminal output, played back as fast as the terminal allows",
+            RecordFormat::Cast => "asciinema recording that keeps the frame timing",
+        }
+    }
+    
+    /// Guess the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ans" | "ansi" => Some(RecordFormat::Ansi),
+            "cast" => Some(RecordFormat::Cast),
+            _ => None,
+        }
+    }
+}
+
+impl ValueEnum for RecordFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &RecordFormat::ALL
+    }
+    
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()).help(self.description()))
+    }
+}
+
+impl fmt::Display for RecordFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Render target that writes every presented frame to a recording
+///
+/// Frames are stored as the same changed-cells-only output the terminal would
+/// get, so recordings stay small and play back like the real thing.
+pub struct Recorder<W: Write> {
+    out: W,
+    format: RecordFormat,
+    width: u16,
+    height: u16,
+    frame_duration: Duration,
+    screen: ScreenBuffer,
+    // Output of the current frame, written out in one go
+    buffer: Vec<u8>,
+    frames: u64,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Start a recording of `width` x `height` cells at `fps` frames per second
+    pub fn new(mut out: W, format: RecordFormat, width: u16, height: u16, fps: u32) -> Result<Self> {
+        if format == RecordFormat::Cast {
+            writeln!(
+                out,
+                "{{\"version\": 2, \"width\": {}, \"height\": {}, \"title\": \"Glowing Waffle\"}}",
+                width, height
+            )?;
+        }
+        
+        Ok(Self {
+            out,
+            format,
+            width,
+            height,
+            frame_duration: Duration::from_secs(1) / fps.max(1),
+            screen: ScreenBuffer::new(),
+            buffer: Vec::new(),
+            frames: 0,
+        })
+    }
+    
+    /// Get the number of frames recorded so far
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+    
+    /// Flush the recording and get the writer back
+    pub fn finish(mut self) -> Result<W> {
+        if self.format == RecordFormat::Ansi {
+            queue!(self.out, ResetColor, Print('\n'))?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> RenderTarget for Recorder<W> {
+    fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+    
+    fn present(&mut self, frame: &FrameBuffer) -> Result<()> {
+        self.buffer.clear();
+        self.screen.write_frame(frame, &mut self.buffer)?;
+        
+        match self.format {
+            RecordFormat::Ansi => self.out.write_all(&self.buffer)?,
+            RecordFormat::Cast => {
+                let time = (self.frame_duration * self.frames as u32).as_secs_f64();
+                let data = String::from_utf8_lossy(&self.buffer);
+                writeln!(self.out, "[{:.6}, \"o\", {}]", time, json_string(&data))?;
+            },
+        }
+        self.frames += 1;
+        Ok(())
+    }
+    
+    fn output_stats(&self) -> Option<OutputStats> {
+        Some(self.screen.last_output())
+    }
+}
+
+/// Quote a string for JSON
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,219 @@
+use anyhow::Result;
+use crossterm::style::Color;
+
+use crate::screen::OutputStats;
+
+/// A single character cell of a rendered frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub color: Color,
+    pub depth: f64,
+}
+
+impl Cell {
+    /// An empty cell that is behind everything
+    pub const EMPTY: Cell = Cell {
+        ch: ' ',
+        color: Color::White,
+        depth: f64::NEG_INFINITY,
+    };
+    
+    pub fn new(ch: char, color: Color, depth: f64) -> Self {
+        Self { ch, color, depth }
+    }
+    
+    /// Check if nothing has been drawn into this cell
+    pub fn is_empty(&self) -> bool {
+        self.ch == ' '
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+/// In-memory grid of cells that the renderer draws into
+///
+/// Depth follows the renderer convention: a larger value is closer to the viewer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameBuffer {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+impl FrameBuffer {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::EMPTY; width as usize * height as usize],
+        }
+    }
+    
+    /// Get the framebuffer width in cells
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+    
+    /// Get the framebuffer height in cells
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+    
+    /// Resize the framebuffer, clearing it if the size changed
+    pub fn resize(&mut self, width: u16, height: u16) {
+        if self.width != width || self.height != height {
+            self.width = width;
+            self.height = height;
+            self.cells = vec![Cell::EMPTY; width as usize * height as usize];
+        }
+    }
+    
+    /// Reset every cell to empty
+    pub fn clear(&mut self) {
+        self.cells.fill(Cell::EMPTY);
+    }
+    
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+    
+    /// Get the cell at a position
+    pub fn get(&self, x: u16, y: u16) -> Option<&Cell> {
+        self.index(x, y).map(|i| &self.cells[i])
+    }
+    
+    /// Overwrite the cell at a position, ignoring depth
+    pub fn set(&mut self, x: u16, y: u16, cell: Cell) {
+        if let Some(i) = self.index(x, y) {
+            self.cells[i] = cell;
+        }
+    }
+    
+    /// Draw a cell if it is closer than what's already there (z-buffer test)
+    ///
+    /// Returns true if the cell was written.
+    pub fn plot(&mut self, x: u16, y: u16, cell: Cell) -> bool {
+        match self.index(x, y) {
+            Some(i) if cell.depth > self.cells[i].depth => {
+                self.cells[i] = cell;
+                true
+            }
+            _ => false,
+        }
+    }
+    
+    /// Get all cells in row-major order
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+    
+    /// Get mutable access to all cells in row-major order
+    pub fn cells_mut(&mut self) -> &mut [Cell] {
+        &mut self.cells
+    }
+    
+    /// Iterate over the rows of the framebuffer
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.cells.chunks(self.width.max(1) as usize)
+    }
+    
+    /// Render the characters as plain text, one line per row
+    pub fn to_text(&self) -> String {
+        let mut text = String::with_capacity(self.cells.len() + self.height as usize);
+        for row in self.rows() {
+            text.extend(row.iter().map(|cell| cell.ch));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// A sink that rendered frames can be presented to
+pub trait RenderTarget {
+    /// Size of the target in cells as (width, height)
+    fn size(&self) -> (u16, u16);
+    
+    /// Height of a character cell divided by its width, if the target knows it
+    fn cell_aspect(&self) -> Option<f64> {
+        None
+    }
+    
+    /// React to the display changing size
+    fn resize(&mut self, _width: u16, _height: u16) {}
+    
+    /// Present a fully rendered frame
+    fn present(&mut self, frame: &FrameBuffer) -> Result<()>;
+    
+    /// What presenting the last frame wrote, for targets that encode frames as terminal output
+    fn output_stats(&self) -> Option<OutputStats> {
+        None
+    }
+}
+
+/// Render target that keeps the last presented frame in memory
+///
+/// Useful for tests, snapshots, benchmarks and exporting frames without a terminal.
+pub struct HeadlessTarget {
+    frame: FrameBuffer,
+    frames_presented: u64,
+    cell_aspect: Option<f64>,
+}
+
+impl HeadlessTarget {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            frame: FrameBuffer::new(width, height),
+            frames_presented: 0,
+            cell_aspect: None,
+        }
+    }
+    
+    /// Pretend to be a display with cells of the given aspect ratio (height / width)
+    pub fn set_cell_aspect(&mut self, aspect: Option<f64>) {
+        self.cell_aspect = aspect;
+    }
+    
+    /// Change the size reported to the renderer
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.frame.resize(width, height);
+    }
+    
+    /// Get the last presented frame
+    pub fn frame(&self) -> &FrameBuffer {
+        &self.frame
+    }
+    
+    /// Get the number of frames presented so far
+    pub fn frames_presented(&self) -> u64 {
+        self.frames_presented
+    }
+}
+
+impl RenderTarget for HeadlessTarget {
+    fn size(&self) -> (u16, u16) {
+        (self.frame.width(), self.frame.height())
+    }
+    
+    fn cell_aspect(&self) -> Option<f64> {
+        self.cell_aspect
+    }
+    
+    fn resize(&mut self, width: u16, height: u16) {
+        HeadlessTarget::resize(self, width, height);
+    }
+    
+    fn present(&mut self, frame: &FrameBuffer) -> Result<()> {
+        self.frame.clone_from(frame);
+        self.frames_presented += 1;
+        Ok(())
+    }
+}
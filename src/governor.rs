@@ -0,0 +1,191 @@
+use std::{fmt, time::Duration};
+
+use crate::{color::ColorDepth, waffle::Detail};
+
+/// Share of the frame time that rendering and output may use
+const BUDGET_SHARE: f64 = 0.8;
+
+/// Quality is only raised if the next level up would stay this far below its budget
+const HEADROOM: f64 = 0.6;
+
+/// Weight of the newest frame in the moving average of frame costs
+const SMOOTHING: f64 = 0.2;
+
+/// Consecutive frames over budget before quality is lowered
+const DOWNGRADE_AFTER: u32 = 10;
+
+/// Consecutive frames with headroom before quality is raised
+const UPGRADE_AFTER: u32 = 90;
+
+/// Frames to wait after a change before judging the new level
+const SETTLE_FRAMES: u32 = 15;
+
+/// What the renderer should spend time on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quality {
+    pub bloom: bool,
+    pub color_depth: ColorDepth,
+    pub detail: Detail,
+    /// Fraction of the target frame rate to run at
+    pub fps_scale: f64,
+}
+
+impl Quality {
+    /// Everything on, at the full frame rate
+    pub const FULL: Quality = Quality {
+        bloom: true,
+        color_depth: ColorDepth::TrueColor,
+        detail: Detail::Full,
+        fps_scale: 1.0,
+    };
+}
+
+impl fmt::Display for Quality {
+    /// List what has been turned down, e.g. "no bloom, 256 colors"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if !self.bloom {
+            parts.push("no bloom".to_string());
+        }
+        match self.color_depth {
+            ColorDepth::TrueColor => {},
+            ColorDepth::Ansi256 => parts.push("256 colors".to_string()),
+            ColorDepth::Ansi16 => parts.push("16 colors".to_string()),
+        }
+        match self.detail {
+            Detail::Full => {},
+            Detail::Reduced => parts.push("reduced mesh".to_string()),
+            Detail::Minimal => parts.push("minimal mesh".to_string()),
+        }
+        if self.fps_scale < 1.0 {
+            parts.push(format!("{:.0}% fps", self.fps_scale * 100.0));
+        }
+        
+        if parts.is_empty() {
+            f.write_str("full")
+        } else {
+            f.write_str(&parts.join(", "))
+        }
+    }
+}
+
+/// Quality levels from best to cheapest, giving up effects before geometry and frame rate
+const LEVELS: [Quality; 8] = [
+    Quality::FULL,
+    Quality { bloom: false, ..Quality::FULL },
+    Quality { bloom: false, color_depth: ColorDepth::Ansi256, ..Quality::FULL },
+    Quality { bloom: false, color_depth: ColorDepth::Ansi16, ..Quality::FULL },
+    Quality { bloom: false, color_depth: ColorDepth::Ansi16, detail: Detail::Reduced, fps_scale: 1.0 },
+    Quality { bloom: false, color_depth: ColorDepth::Ansi16, detail: Detail::Minimal, fps_scale: 1.0 },
+    Quality { bloom: false, color_depth: ColorDepth::Ansi16, detail: Detail::Minimal, fps_scale: 0.75 },
+    Quality { bloom: false, color_depth: ColorDepth::Ansi16, detail: Detail::Minimal, fps_scale: 0.5 },
+];
+
+/// Adjusts quality to keep each frame within the time available at the target frame rate
+///
+/// Frame costs (render plus output time) are smoothed, and quality only drops
+/// after several slow frames in a row and only rises after a longer stretch
+/// of fast ones, so a single hiccup doesn't make the picture flicker between
+/// levels.
+#[derive(Debug, Clone)]
+pub struct Governor {
+    target_fps: u32,
+    level: usize,
+    // Moving average of the frame cost in seconds, unset right after a change
+    average: Option<f64>,
+    over_budget: u32,
+    under_budget: u32,
+    settle: u32,
+}
+
+impl Governor {
+    pub fn new(target_fps: u32) -> Self {
+        Self {
+            target_fps: target_fps.max(1),
+            level: 0,
+            average: None,
+            over_budget: 0,
+            under_budget: 0,
+            settle: 0,
+        }
+    }
+    
+    /// Change the frame rate the budget is based on
+    pub fn set_target_fps(&mut self, target_fps: u32) {
+        self.target_fps = target_fps.max(1);
+        self.over_budget = 0;
+        self.under_budget = 0;
+    }
+    
+    /// Get the current quality
+    pub fn quality(&self) -> Quality {
+        LEVELS[self.level]
+    }
+    
+    /// Get the current level, 0 being full quality
+    pub fn level(&self) -> usize {
+        self.level
+    }
+    
+    /// Get the lowest quality level
+    pub fn max_level(&self) -> usize {
+        LEVELS.len() - 1
+    }
+    
+    /// Get the smoothed time spent per frame
+    pub fn average_cost(&self) -> Duration {
+        Duration::from_secs_f64(self.average.unwrap_or(0.0))
+    }
+    
+    /// Get the time a frame may take at a quality level
+    pub fn budget(&self, level: usize) -> Duration {
+        let fps = self.target_fps as f64 * LEVELS[level.min(self.max_level())].fps_scale;
+        Duration::from_secs_f64(BUDGET_SHARE / fps.max(0.1))
+    }
+    
+    /// Record how long rendering and presenting a frame took
+    ///
+    /// Returns the new quality if it changed.
+    pub fn record(&mut self, render: Duration, present: Duration) -> Option<Quality> {
+        let cost = (render + present).as_secs_f64();
+        let average = match self.average {
+            Some(average) => average + (cost - average) * SMOOTHING,
+            None => cost,
+        };
+        self.average = Some(average);
+        
+        if self.settle > 0 {
+            self.settle -= 1;
+            return None;
+        }
+        
+        let level = self.level;
+        if average > self.budget(level).as_secs_f64() {
+            self.under_budget = 0;
+            self.over_budget += 1;
+            if self.over_budget >= DOWNGRADE_AFTER && level < self.max_level() {
+                return Some(self.change_level(level + 1));
+            }
+        } else if level > 0 && average < self.budget(level - 1).as_secs_f64() * HEADROOM {
+            self.over_budget = 0;
+            self.under_budget += 1;
+            if self.under_budget >= UPGRADE_AFTER {
+                return Some(self.change_level(level - 1));
+            }
+        } else {
+            self.over_budget = 0;
+            self.under_budget = 0;
+        }
+        
+        None
+    }
+    
+    fn change_level(&mut self, level: usize) -> Quality {
+        self.level = level;
+        self.average = None;
+        self.over_budget = 0;
+        self.under_budget = 0;
+        self.settle = SETTLE_FRAMES;
+        self.quality()
+    }
+}
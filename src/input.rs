@@ -0,0 +1,370 @@
+use anyhow::{anyhow, bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::{fmt, str::FromStr};
+
+/// Something the user can ask the visualizer to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    TogglePause,
+    Step,
+    SpeedUp,
+    SlowDown,
+    FpsUp,
+    FpsDown,
+    NextAnimation,
+    NextColor,
+    NextSize,
+    ResetView,
+    ReleaseControl,
+    ToggleStats,
+    ToggleProfile,
+    ToggleHelp,
+}
+
+impl Action {
+    /// Every action, in the order they are listed in the help overlay
+    pub const ALL: [Action; 15] = [
+        Action::TogglePause,
+        Action::Step,
+        Action::SpeedUp,
+        Action::SlowDown,
+        Action::FpsUp,
+        Action::FpsDown,
+        Action::NextAnimation,
+        Action::NextColor,
+        Action::NextSize,
+        Action::ResetView,
+        Action::ReleaseControl,
+        Action::ToggleStats,
+        Action::ToggleProfile,
+        Action::ToggleHelp,
+        Action::Quit,
+    ];
+    
+    /// Get the name used in key binding specs
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::TogglePause => "pause",
+            Action::Step => "step",
+            Action::SpeedUp => "faster",
+            Action::SlowDown => "slower",
+            Action::FpsUp => "fps-up",
+            Action::FpsDown => "fps-down",
+            Action::NextAnimation => "next-animation",
+            Action::NextColor => "next-color",
+            Action::NextSize => "next-size",
+            Action::ResetView => "reset-view",
+            Action::ReleaseControl => "release",
+            Action::ToggleStats => "stats",
+            Action::ToggleProfile => "profile",
+            Action::ToggleHelp => "help",
+        }
+    }
+    
+    /// Get a short description for the help overlay
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::TogglePause => "Pause / resume",
+            Action::Step => "Advance one frame while paused",
+            Action::SpeedUp => "Rotate faster",
+            Action::SlowDown => "Rotate slower",
+            Action::FpsUp => "Raise the frame rate",
+            Action::FpsDown => "Lower the frame rate",
+            Action::NextAnimation => "Next animation",
+            Action::NextColor => "Next color mode",
+            Action::NextSize => "Next waffle size",
+            Action::ResetView => "Reset rotation, speed and camera",
+            Action::ReleaseControl => "Stop a mouse spin and resume the animation",
+            Action::ToggleStats => "Show / hide the frame rate",
+            Action::ToggleProfile => "Show / hide the time spent in each render stage",
+            Action::ToggleHelp => "Show / hide this help",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = anyhow::Error;
+    
+    fn from_str(name: &str) -> Result<Self> {
+        let name = name.trim();
+        Action::ALL
+            .into_iter()
+            .find(|action| action.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<_> = Action::ALL.iter().map(Action::name).collect();
+                anyhow!("Unknown action '{}', expected one of: {}", name, names.join(", "))
+            })
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A key together with the modifiers that must be held
+///
+/// Parsed from specs such as `q`, `?`, `space`, `ctrl+c` or `alt+up`. Shift is
+/// implied by the character itself, so `Q` and `?` need no `shift+`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+    
+    /// Create a binding for a plain character
+    pub fn char(ch: char) -> Self {
+        Self::new(KeyCode::Char(ch), KeyModifiers::NONE)
+    }
+    
+    /// Create a binding for a character with Ctrl held
+    pub fn ctrl(ch: char) -> Self {
+        Self::new(KeyCode::Char(ch), KeyModifiers::CONTROL)
+    }
+    
+    /// Get the binding a key event corresponds to
+    pub fn from_event(event: &KeyEvent) -> Self {
+        let mut modifiers = event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        // Terminals disagree on whether shifted characters carry SHIFT; the character says it all
+        if let KeyCode::Char(_) = event.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self::new(event.code, modifiers)
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = anyhow::Error;
+    
+    fn from_str(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            bail!("Empty key binding");
+        }
+        
+        // The key itself may be '+', as in "+" or "ctrl++"
+        let (prefix, key) = match spec.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => (rest, "+"),
+            _ => match spec.rfind('+') {
+                Some(i) => (&spec[..i + 1], &spec[i + 1..]),
+                None => ("", spec),
+            },
+        };
+        
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in prefix.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                other => bail!("Unknown modifier '{}' in key '{}'", other, spec),
+            };
+        }
+        
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "plus" => KeyCode::Char('+'),
+                "minus" => KeyCode::Char('-'),
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => bail!("Unknown key '{}' in '{}'", key, spec),
+                },
+            },
+        };
+        
+        // Characters are matched without SHIFT (see `from_event`), so shift has to
+        // be folded into the character: a capital for letters, an error otherwise
+        let code = match code {
+            KeyCode::Char(ch) if modifiers.contains(KeyModifiers::SHIFT) => {
+                if !ch.is_alphabetic() {
+                    bail!("Can't hold shift with '{}' in '{}'; bind the character shift types instead", ch, spec);
+                }
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(ch.to_uppercase().next().unwrap_or(ch))
+            },
+            code => code,
+        };
+        
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::SHIFT, "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(ch) => write!(f, "{}", ch),
+            KeyCode::Enter => f.write_str("enter"),
+            KeyCode::Esc => f.write_str("esc"),
+            KeyCode::Tab => f.write_str("tab"),
+            KeyCode::Backspace => f.write_str("backspace"),
+            KeyCode::Up => f.write_str("up"),
+            KeyCode::Down => f.write_str("down"),
+            KeyCode::Left => f.write_str("left"),
+            KeyCode::Right => f.write_str("right"),
+            KeyCode::Home => f.write_str("home"),
+            KeyCode::End => f.write_str("end"),
+            KeyCode::PageUp => f.write_str("pageup"),
+            KeyCode::PageDown => f.write_str("pagedown"),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// A `KEY=ACTION` pair from the command line; `KEY=none` removes a binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BindingSpec {
+    pub key: KeyBinding,
+    pub action: Option<Action>,
+}
+
+impl FromStr for BindingSpec {
+    type Err = anyhow::Error;
+    
+    fn from_str(spec: &str) -> Result<Self> {
+        // Split on the last '=' so that '=' itself can be bound
+        let (key, action) = spec
+            .rsplit_once('=')
+            .ok_or_else(|| anyhow!("Binding '{}' should look like KEY=ACTION, e.g. space=pause", spec))?;
+        let key = key.parse()?;
+        let action = match action.trim() {
+            "none" | "" => None,
+            name => Some(name.parse()?),
+        };
+        Ok(Self { key, action })
+    }
+}
+
+/// Maps keys to actions
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyMap {
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl KeyMap {
+    /// Create a keymap without any bindings
+    pub fn empty() -> Self {
+        Self { bindings: Vec::new() }
+    }
+    
+    /// Bind a key to an action, replacing any previous binding of the key
+    pub fn bind(&mut self, key: KeyBinding, action: Action) {
+        self.unbind(key);
+        self.bindings.push((key, action));
+    }
+    
+    /// Remove the binding of a key
+    pub fn unbind(&mut self, key: KeyBinding) {
+        self.bindings.retain(|(bound, _)| *bound != key);
+    }
+    
+    /// Apply a binding spec from the command line
+    pub fn apply(&mut self, spec: BindingSpec) {
+        match spec.action {
+            Some(action) => self.bind(spec.key, action),
+            None => self.unbind(spec.key),
+        }
+    }
+    
+    /// Get the action bound to a key
+    pub fn action(&self, key: KeyBinding) -> Option<Action> {
+        self.bindings.iter().find(|(bound, _)| *bound == key).map(|&(_, action)| action)
+    }
+    
+    /// Get the action for a key event, ignoring key releases
+    pub fn action_for_event(&self, event: &KeyEvent) -> Option<Action> {
+        if event.kind == KeyEventKind::Release {
+            return None;
+        }
+        self.action(KeyBinding::from_event(event))
+    }
+    
+    /// Get all keys bound to an action, in the order they were bound
+    pub fn keys_for(&self, action: Action) -> impl Iterator<Item = KeyBinding> + '_ {
+        self.bindings.iter().filter(move |(_, bound)| *bound == action).map(|&(key, _)| key)
+    }
+    
+    /// Get one line per bound action for the help overlay
+    pub fn help_lines(&self) -> Vec<String> {
+        let rows: Vec<(String, &str)> = Action::ALL
+            .iter()
+            .filter_map(|&action| {
+                let keys: Vec<String> = self.keys_for(action).map(|key| key.to_string()).collect();
+                (!keys.is_empty()).then(|| (keys.join(" "), action.description()))
+            })
+            .collect();
+        
+        let width = rows.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or(0);
+        rows.into_iter()
+            .map(|(keys, description)| format!("{:<width$}  {}", keys, description, width = width))
+            .collect()
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut keymap = Self::empty();
+        let defaults = [
+            (KeyBinding::char('q'), Action::Quit),
+            (KeyBinding::char('Q'), Action::Quit),
+            (KeyBinding::new(KeyCode::Esc, KeyModifiers::NONE), Action::Quit),
+            (KeyBinding::ctrl('c'), Action::Quit),
+            (KeyBinding::char(' '), Action::TogglePause),
+            (KeyBinding::char('p'), Action::TogglePause),
+            (KeyBinding::char('.'), Action::Step),
+            (KeyBinding::char('+'), Action::SpeedUp),
+            (KeyBinding::char('='), Action::SpeedUp),
+            (KeyBinding::char('-'), Action::SlowDown),
+            (KeyBinding::char(']'), Action::FpsUp),
+            (KeyBinding::char('['), Action::FpsDown),
+            (KeyBinding::char('a'), Action::NextAnimation),
+            (KeyBinding::char('c'), Action::NextColor),
+            (KeyBinding::char('s'), Action::NextSize),
+            (KeyBinding::char('r'), Action::ResetView),
+            (KeyBinding::char('g'), Action::ReleaseControl),
+            (KeyBinding::char('f'), Action::ToggleStats),
+            (KeyBinding::char('t'), Action::ToggleProfile),
+            (KeyBinding::char('?'), Action::ToggleHelp),
+            (KeyBinding::char('h'), Action::ToggleHelp),
+        ];
+        for (key, action) in defaults {
+            keymap.bind(key, action);
+        }
+        keymap
+    }
+}
@@ -4,13 +4,46 @@
 //! using ASCII characters with animation and color effects.
 
 pub mod animation;
+pub mod app;
 pub mod benchmark;
+pub mod bloom;
+pub mod camera;
+pub mod clock;
+pub mod color;
+pub mod config;
+pub mod event_loop;
+pub mod export;
+pub mod framebuffer;
+pub mod governor;
+pub mod input;
+pub mod lighting;
+pub mod mesh;
+pub mod raster;
 pub mod renderer;
+pub mod rotation;
+pub mod screen;
 pub mod terminal;
+pub mod timestep;
+pub mod trackball;
 pub mod waffle;
 
 // Re-export common types for easier access
-pub use animation::Animation;
-pub use renderer::{ColorMode, Renderer};
+pub use animation::{Animation, FrameContext};
+pub use app::App;
+pub use bloom::Bloom;
+pub use camera::{Camera, Projection};
+pub use config::{Config, Settings};
+pub use clock::{Clock, FixedStepClock, ManualClock, RealClock};
+pub use event_loop::{AppEvent, Control, EventHandler};
+pub use framebuffer::{FrameBuffer, HeadlessTarget, RenderTarget};
+pub use governor::{Governor, Quality};
+pub use input::{Action, KeyBinding, KeyMap};
+pub use lighting::{Light, Lighting};
+pub use mesh::{Face, Mesh, WaffleShape};
+pub use renderer::{ColorMode, FrameTiming, RenderStyle, Renderer, Stage};
+pub use rotation::Rotation;
+pub use screen::{OutputStats, ScreenBuffer};
 pub use terminal::Terminal;
-pub use waffle::{Size, Waffle}; 
\ No newline at end of file
+pub use timestep::{FixedTimestep, FrameStats};
+pub use trackball::Trackball;
+pub use waffle::{Detail, Size, Waffle}; 
\ No newline at end of file
@@ -0,0 +1,156 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::str::FromStr;
+
+use crate::waffle::Point3D;
+
+/// How quickly point lights fade with the square of the distance
+const POINT_LIGHT_FALLOFF: f64 = 0.05;
+
+/// A light source illuminating the waffle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Light {
+    /// Parallel rays shining along `direction`, like sunlight
+    Directional { direction: Point3D, intensity: f64 },
+    /// Rays spreading out from `position`, fading with distance
+    Point { position: Point3D, intensity: f64 },
+}
+
+impl Light {
+    pub fn directional(direction: Point3D, intensity: f64) -> Self {
+        Light::Directional {
+            direction: direction.normalized(),
+            intensity,
+        }
+    }
+    
+    pub fn point(position: Point3D, intensity: f64) -> Self {
+        Light::Point { position, intensity }
+    }
+    
+    /// Unit vector from a surface point towards the light and the intensity arriving there
+    fn incoming(&self, position: Point3D) -> (Point3D, f64) {
+        match *self {
+            Light::Directional { direction, intensity } => (-direction.normalized(), intensity),
+            Light::Point { position: light, intensity } => {
+                let offset = light - position;
+                let attenuation = 1.0 + POINT_LIGHT_FALLOFF * offset.dot(offset);
+                (offset.normalized(), intensity / attenuation)
+            }
+        }
+    }
+}
+
+impl FromStr for Light {
+    type Err = anyhow::Error;
+    
+    /// Parse `dir:X,Y,Z[:INTENSITY]` or `point:X,Y,Z[:INTENSITY]`
+    fn from_str(spec: &str) -> Result<Self> {
+        let mut parts = spec.split(':');
+        let kind = parts.next().unwrap_or_default();
+        let vector = parts
+            .next()
+            .ok_or_else(|| anyhow!("Light '{}' is missing coordinates, expected e.g. dir:1,1,-1", spec))?;
+        let intensity = match parts.next() {
+            Some(value) => value
+                .trim()
+                .parse::<f64>()
+                .with_context(|| format!("Invalid light intensity '{}'", value))?,
+            None => 1.0,
+        };
+        if parts.next().is_some() {
+            bail!("Light '{}' has too many fields", spec);
+        }
+        
+        let coords = vector
+            .split(',')
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Invalid light coordinates '{}'", vector))?;
+        let [x, y, z] = coords[..] else {
+            bail!("Light coordinates '{}' must have three components", vector);
+        };
+        let vector = Point3D::new(x, y, z);
+        
+        match kind.trim() {
+            "dir" | "directional" => {
+                if vector.length() == 0.0 {
+                    bail!("Directional light '{}' needs a non-zero direction", spec);
+                }
+                Ok(Light::directional(vector, intensity))
+            }
+            "point" => Ok(Light::point(vector, intensity)),
+            other => bail!("Unknown light type '{}', expected 'dir' or 'point'", other),
+        }
+    }
+}
+
+/// Light sources and material settings used to shade surfaces
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lighting {
+    /// Light reaching every surface regardless of orientation
+    pub ambient: f64,
+    /// Strength of specular highlights
+    pub specular: f64,
+    /// Sharpness of specular highlights
+    pub shininess: f64,
+    lights: Vec<Light>,
+}
+
+impl Lighting {
+    /// Create lighting with only ambient light
+    pub fn new(ambient: f64) -> Self {
+        Self {
+            ambient,
+            specular: 0.3,
+            shininess: 16.0,
+            lights: Vec::new(),
+        }
+    }
+    
+    /// Add a light source
+    pub fn add_light(&mut self, light: Light) {
+        self.lights.push(light);
+    }
+    
+    /// Replace all light sources
+    pub fn set_lights(&mut self, lights: Vec<Light>) {
+        self.lights = lights;
+    }
+    
+    /// Get the light sources
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+    
+    /// Compute how brightly a surface is lit, from 0 (dark) to 1 (fully lit)
+    ///
+    /// Uses Lambert diffuse plus Blinn-Phong specular for every light.
+    pub fn luminance(&self, position: Point3D, normal: Point3D, eye: Point3D) -> f64 {
+        let normal = normal.normalized();
+        let view = (eye - position).normalized();
+        let mut total = self.ambient;
+        
+        for light in &self.lights {
+            let (to_light, intensity) = light.incoming(position);
+            let diffuse = normal.dot(to_light).max(0.0);
+            if diffuse <= 0.0 {
+                continue;
+            }
+            
+            let half = (to_light + view).normalized();
+            let specular = self.specular * normal.dot(half).max(0.0).powf(self.shininess);
+            total += intensity * (diffuse + specular);
+        }
+        
+        total.clamp(0.0, 1.0)
+    }
+}
+
+impl Default for Lighting {
+    /// A key light from the upper left in front of the waffle
+    fn default() -> Self {
+        let mut lighting = Self::new(0.1);
+        lighting.add_light(Light::directional(Point3D::new(1.0, -1.0, -1.0), 0.75));
+        lighting
+    }
+}
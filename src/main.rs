@@ -1,84 +1,534 @@
 use anyhow::{Context, Result};
-use clap::Parser;
-use std::time::Duration;
-
-mod animation;
-mod benchmark;
-mod renderer;
-mod terminal;
-mod waffle;
+use clap::{ArgAction, Args, Parser, Subcommand};
+use glowing_waffle::{
+    animation::{Animation, AnimationType},
+    app::App,
+    benchmark::{self, BenchmarkOptions, BenchmarkReport, Comparison, Metric, ReportFormat},
+    clock::FixedStepClock,
+    config::{self, Config, Palette, Settings},
+    export::{self, ExportFormat, RecordFormat, Recorder},
+    framebuffer::HeadlessTarget,
+    input::{Action, BindingSpec},
+    lighting::Light,
+    renderer::{ColorMode, Renderer},
+    terminal::{self, Capabilities},
+    waffle::{Size, Waffle},
+};
+use std::{
+    env,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
 
+/// Command line interface; settings left out fall back to the environment, the config file and the defaults
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    /// Enable benchmarking mode
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
+struct Cli {
+    /// Read settings from this file instead of ~/.config/glowing-waffle/config.toml
+    #[arg(long, value_name = "PATH", global = true)]
+    config: Option<PathBuf>,
+
+    /// List the available color modes, sizes, animations and key actions, then exit
+    #[arg(long)]
+    list: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    // Running without a subcommand is the same as `run`
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the interactive visualizer (the default)
+    Run(RunArgs),
+    /// Measure how fast frames are rendered in this terminal
+    Bench(BenchArgs),
+    /// Render the animation to a file
+    Record(RecordArgs),
+    /// Render a single frame to a text, ANSI, HTML or SVG file
+    Export(ExportArgs),
+    /// Show what was detected about the terminal and where settings come from
+    Info,
+}
+
+/// Settings that change what the waffle looks like
+#[derive(Args, Debug)]
+struct RenderArgs {
+    /// Set the frames per second for the animation, from 1 to 120 [default: 30]
+    #[arg(long, value_parser = config::parse_fps)]
+    fps: Option<u32>,
+
+    /// Set the color mode [default: normal]
+    #[arg(long, value_enum)]
+    color: Option<ColorMode>,
+
+    /// Set the size of the waffle [default: medium]
+    #[arg(long, value_enum)]
+    size: Option<Size>,
+
+    /// Set how the waffle moves [default: rotate]
+    #[arg(long, value_enum)]
+    animation: Option<AnimationType>,
+
+    /// Set the rotation speed multiplier, from 0.05 to 20 [default: 1]
+    #[arg(long, value_parser = config::parse_speed)]
+    speed: Option<f64>,
+
+    /// Set the shading characters, from sparse to dense, e.g. " .:-=+*#%@"
+    #[arg(long, action = ArgAction::Set, value_parser = config::parse_palette)]
+    palette: Option<Palette>,
+
+    /// Add a light source, replacing the default light (repeatable):
+    /// dir:X,Y,Z[:INTENSITY] or point:X,Y,Z[:INTENSITY]
+    #[arg(long = "light", value_name = "SPEC")]
+    lights: Vec<Light>,
+
+    /// Set the ambient light level (0.0 - 1.0)
+    #[arg(long, value_parser = config::parse_ambient)]
+    ambient: Option<f64>,
+
+    /// Disable the glow effect (faster on slow terminals)
+    #[arg(long)]
+    no_bloom: bool,
+
+    /// Set how many rows the glow spreads [default: 2]
+    #[arg(long, value_parser = config::parse_bloom_radius)]
+    bloom_radius: Option<u16>,
+
+    /// Set the glow strength [default: 0.5]
+    #[arg(long, value_parser = config::parse_non_negative)]
+    bloom_intensity: Option<f64>,
+
+    /// Set the camera's vertical field of view in degrees (1 - 179) [default: 50]
+    #[arg(long, value_parser = config::parse_fov)]
+    fov: Option<f64>,
+
+    /// Use an orthographic camera instead of perspective
+    #[arg(long)]
+    orthographic: bool,
+
+    /// Set the character cell height/width ratio used when the terminal doesn't report its pixel size [default: 2]
+    #[arg(long, value_parser = config::parse_positive)]
+    cell_aspect: Option<f64>,
+
+    /// Keep the camera where it is instead of fitting the waffle to the terminal
+    #[arg(long)]
+    no_auto_fit: bool,
+}
+
+impl RenderArgs {
+    /// Get the settings given on the command line as the topmost config layer
+    fn config(&self) -> Config {
+        Config {
+            size: self.size,
+            color: self.color,
+            palette: self.palette.clone(),
+            animation: self.animation,
+            speed: self.speed,
+            fps: self.fps,
+            lights: (!self.lights.is_empty()).then(|| self.lights.clone()),
+            ambient: self.ambient,
+            bloom: switch(self.no_bloom, false),
+            bloom_radius: self.bloom_radius,
+            bloom_intensity: self.bloom_intensity,
+            fov: self.fov,
+            orthographic: switch(self.orthographic, true),
+            cell_aspect: self.cell_aspect,
+            auto_fit: switch(self.no_auto_fit, false),
+            ..Config::default()
+        }
+    }
+}
+
+/// Switches can only turn things on or off, so an absent one leaves the setting alone
+fn switch(flag: bool, value: bool) -> Option<bool> {
+    flag.then_some(value)
+}
+
+#[derive(Args, Debug)]
+struct RunArgs {
+    #[command(flatten)]
+    render: RenderArgs,
+
+    /// Bind a key to an action, e.g. `--bind space=pause` or `--bind q=none` (repeatable; press ? for the list)
+    #[arg(long = "bind", value_name = "KEY=ACTION")]
+    bindings: Vec<BindingSpec>,
+
+    /// Don't capture the mouse (leaves text selection to the terminal)
+    #[arg(long)]
+    no_mouse: bool,
+
+    /// Set how many times per second the animation is updated, independent of --fps [default: 60]
+    #[arg(long)]
+    update_rate: Option<u32>,
+
+    /// Show the actual and target frame rate
     #[arg(long)]
-    benchmark: bool,
+    stats: bool,
 
-    /// Set the frames per second for the animation
-    #[arg(long, default_value_t = 30)]
-    fps: u8,
+    /// Show how long each render stage takes
+    #[arg(long)]
+    profile: bool,
 
-    /// Set the color mode (normal, rainbow, fire)
-    #[arg(long, default_value = "normal")]
-    color: String,
+    /// Always draw at full quality instead of lowering it when frames take too long
+    #[arg(long)]
+    no_adaptive: bool,
+}
 
-    /// Set the size of the waffle (small, medium, large)
-    #[arg(long, default_value = "medium")]
-    size: String,
+impl RunArgs {
+    fn config(&self) -> Config {
+        Config {
+            bindings: (!self.bindings.is_empty()).then(|| self.bindings.clone()),
+            mouse: switch(self.no_mouse, false),
+            update_rate: self.update_rate,
+            stats: switch(self.stats, true),
+            profile: switch(self.profile, true),
+            adaptive: switch(self.no_adaptive, false),
+            ..self.render.config()
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct BenchArgs {
+    #[command(flatten)]
+    render: RenderArgs,
+
+    #[command(flatten)]
+    frame_size: FrameSizeArgs,
+
+    /// Number of timed frames for every waffle size and color mode
+    #[arg(long, default_value_t = benchmark::BENCHMARK_FRAMES as u32, value_parser = clap::value_parser!(u32).range(1..))]
+    frames: u32,
+
+    /// Number of untimed frames rendered before each run
+    #[arg(long, default_value_t = benchmark::WARMUP_FRAMES as u32)]
+    warmup: u32,
+
+    /// File to write the report to [default: standard output]
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Report format [default: from the file extension, otherwise table]
+    #[arg(long, value_enum)]
+    format: Option<ReportFormat>,
+
+    /// Render to the terminal instead of memory, so the timings include terminal output
+    #[arg(long)]
+    terminal: bool,
+
+    /// Save the results as a baseline for later runs to compare against
+    #[arg(long, value_name = "PATH")]
+    save_baseline: Option<PathBuf>,
+
+    /// Compare the results with a saved baseline and fail if any got slower than --threshold allows
+    #[arg(long, value_name = "PATH")]
+    baseline: Option<PathBuf>,
+
+    /// Slowdown against the baseline, in percent, that counts as a regression
+    #[arg(long, default_value_t = 10.0, value_parser = config::parse_positive)]
+    threshold: f64,
+
+    /// Frame time statistic compared against the baseline
+    #[arg(long, value_enum, default_value_t = Metric::P50)]
+    metric: Metric,
+
+    /// Don't fail when some results have no counterpart in the baseline
+    #[arg(long)]
+    allow_missing: bool,
+}
+
+/// Size of a frame rendered without a terminal
+#[derive(Args, Debug)]
+struct FrameSizeArgs {
+    /// Width of the frame in characters
+    #[arg(long, default_value_t = 80, value_parser = clap::value_parser!(u16).range(1..))]
+    width: u16,
+
+    /// Height of the frame in characters
+    #[arg(long, default_value_t = 24, value_parser = clap::value_parser!(u16).range(1..))]
+    height: u16,
+}
+
+#[derive(Args, Debug)]
+struct RecordArgs {
+    #[command(flatten)]
+    render: RenderArgs,
+
+    #[command(flatten)]
+    frame_size: FrameSizeArgs,
+
+    /// File to write the recording to
+    #[arg(short, long, value_name = "PATH")]
+    output: PathBuf,
+
+    /// Recording format [default: from the file extension, otherwise ansi]
+    #[arg(long, value_enum)]
+    format: Option<RecordFormat>,
+
+    /// Length of the recording in seconds
+    #[arg(long, default_value_t = 5.0, value_parser = config::parse_positive)]
+    duration: f64,
+}
+
+#[derive(Args, Debug)]
+struct ExportArgs {
+    #[command(flatten)]
+    render: RenderArgs,
+
+    #[command(flatten)]
+    frame_size: FrameSizeArgs,
+
+    /// File to write the frame to [default: standard output]
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Output format [default: from the file extension, otherwise txt]
+    #[arg(long, value_enum)]
+    format: Option<ExportFormat>,
+
+    /// Point in the animation to capture, in seconds
+    #[arg(long, default_value_t = 2.0)]
+    time: f64,
+}
+
+/// Print every color mode, size, animation and key action with a description
+fn print_list() {
+    fn section<'a>(title: &str, entries: impl IntoIterator<Item = (&'a str, &'a str)>) {
+        println!("{}:", title);
+        for (name, description) in entries {
+            println!("  {:<16}{}", name, description);
+        }
+        println!();
+    }
+    
+    section("Color modes (--color)", ColorMode::ALL.map(|mode| (mode.name(), mode.description())));
+    section("Sizes (--size)", Size::ALL.map(|size| (size.name(), size.description())));
+    section(
+        "Animations (--animation)",
+        AnimationType::ALL.map(|animation| (animation.name(), animation.description())),
+    );
+    section(
+        "Key actions (--bind KEY=ACTION)",
+        Action::ALL.map(|action| (action.name(), action.description())),
+    );
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    let cli = Cli::parse();
+    if cli.list {
+        print_list();
+        return Ok(());
+    }
     
-    // Initialize terminal
+    let config_path = cli.config.as_deref();
+    match &cli.command {
+        None => run(Settings::load(config_path, &cli.run.config())?),
+        Some(Command::Run(args)) => run(Settings::load(config_path, &args.config())?),
+        Some(Command::Bench(args)) => bench(Settings::load(config_path, &args.render.config())?, args),
+        Some(Command::Record(args)) => record(Settings::load(config_path, &args.render.config())?, args),
+        Some(Command::Export(args)) => export(Settings::load(config_path, &args.render.config())?, args),
+        Some(Command::Info) => info(config_path),
+    }
+}
+
+/// Run the interactive visualizer
+fn run(settings: Settings) -> Result<()> {
     let mut term = terminal::Terminal::new().context("Failed to initialize terminal")?;
     
-    // Create waffle model based on size
-    let waffle_size = match args.size.as_str() {
-        "small" => waffle::Size::Small,
-        "large" => waffle::Size::Large,
-        _ => waffle::Size::Medium,
-    };
+    let mut app = App::new(
+        settings.waffle(),
+        settings.renderer(),
+        settings.animation(),
+        settings.keymap(),
+        settings.fps,
+    );
+    app.set_update_rate(settings.update_rate);
+    app.set_stats_visible(settings.stats);
+    app.set_profile_visible(settings.profile);
+    app.set_adaptive(settings.adaptive);
     
-    let mut waffle = waffle::Waffle::new(waffle_size);
+    // Main animation loop
+    println!("Starting Glowing Waffle visualization. Press '?' for help or 'q' to quit.");
+    
+    term.set_mouse_capture(settings.mouse)?;
+    term.run(app.frame_duration(), &mut app).context("Animation loop failed")?;
+    
+    println!("Glowing Waffle visualization ended.");
     
-    // Set up renderer with color mode
-    let color_mode = match args.color.as_str() {
-        "rainbow" => renderer::ColorMode::Rainbow,
-        "fire" => renderer::ColorMode::Fire,
-        _ => renderer::ColorMode::Normal,
+    Ok(())
+}
+
+/// Benchmark rendering every size in every color mode and report the frame times
+fn bench(settings: Settings, args: &BenchArgs) -> Result<()> {
+    let (_, mut renderer, mut animation) = headless(&settings);
+    let format = args
+        .format
+        .or_else(|| args.output.as_deref().and_then(ReportFormat::from_path))
+        .unwrap_or(ReportFormat::Table);
+    
+    // Sweep everything unless a size or color mode was picked on the command line
+    let options = BenchmarkOptions {
+        frames: args.frames as usize,
+        warmup: args.warmup as usize,
+        sizes: args.render.size.map_or_else(|| Size::ALL.to_vec(), |size| vec![size]),
+        color_modes: args.render.color.map_or_else(|| ColorMode::ALL.to_vec(), |mode| vec![mode]),
     };
     
-    let mut renderer = renderer::Renderer::new(color_mode);
+    // Read the baseline first so a bad path fails before the benchmark runs
+    let baseline = args.baseline.as_deref().map(BenchmarkReport::load).transpose()?;
     
-    // Set up animation
-    let mut animation = animation::Animation::new();
+    let report = if args.terminal {
+        // The terminal is restored when it's dropped, before the report is written
+        let mut term = terminal::Terminal::new().context("Failed to initialize terminal")?;
+        benchmark::run(&mut term, &mut renderer, &mut animation, &options)
+    } else {
+        // Encode every frame as the terminal would get it, then throw the bytes away,
+        // so the emit stage and the output volume are still measured
+        let FrameSizeArgs { width, height } = args.frame_size;
+        let mut target = Recorder::new(io::sink(), RecordFormat::Ansi, width, height, settings.fps)?;
+        benchmark::run(&mut target, &mut renderer, &mut animation, &options)
+    }
+    .context("Benchmark failed")?;
     
-    // Calculate frame duration based on FPS
-    let frame_duration = Duration::from_millis(1000 / args.fps as u64);
+    match &args.output {
+        Some(path) => {
+            let file = File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+            let mut out = BufWriter::new(file);
+            benchmark::write_report(&report, format, &mut out)?;
+            out.flush()?;
+        },
+        None => {
+            let mut out = io::stdout().lock();
+            benchmark::write_report(&report, format, &mut out)?;
+            out.flush()?;
+        },
+    }
     
-    // Benchmark mode
-    if args.benchmark {
-        return benchmark::run_benchmark(&mut term, &mut renderer, &mut waffle, &mut animation)
-            .context("Benchmark failed");
+    if let Some(path) = &args.save_baseline {
+        report.save(path)?;
+        eprintln!("Saved baseline to {}", path.display());
     }
     
-    // Main animation loop
-    println!("Starting Glowing Waffle visualization. Press 'q' to quit.");
+    if let Some(baseline) = baseline {
+        if (baseline.width, baseline.height) != (report.width, report.height) {
+            eprintln!(
+                "Warning: the baseline was rendered at {}x{}, this run at {}x{}",
+                baseline.width, baseline.height, report.width, report.height
+            );
+        }
+        
+        // Comparison goes to stderr so it never mixes with a report on stdout
+        let comparison = Comparison::new(&baseline, &report, args.metric, args.threshold / 100.0);
+        eprintln!();
+        benchmark::write_comparison(&comparison, &mut io::stderr().lock())?;
+        comparison.check(args.allow_missing)?;
+    }
+    Ok(())
+}
+
+/// Create the waffle, renderer and an animation that advances one frame per update at the configured frame rate
+fn headless(settings: &Settings) -> (Waffle, Renderer, Animation) {
+    let mut animation = settings.animation();
+    animation.set_clock(FixedStepClock::from_fps(settings.fps));
+    (settings.waffle(), settings.renderer(), animation)
+}
+
+/// Render the animation into a recording file
+fn record(settings: Settings, args: &RecordArgs) -> Result<()> {
+    let (mut waffle, mut renderer, mut animation) = headless(&settings);
+    let format = args
+        .format
+        .or_else(|| RecordFormat::from_path(&args.output))
+        .unwrap_or(RecordFormat::Ansi);
+    
+    let file = File::create(&args.output).with_context(|| format!("Failed to create {}", args.output.display()))?;
+    let FrameSizeArgs { width, height } = args.frame_size;
+    let mut recorder = Recorder::new(BufWriter::new(file), format, width, height, settings.fps)?;
     
-    term.run_event_loop(frame_duration, |term| {
-        // Update waffle animation
+    let frames = (args.duration * settings.fps as f64).round().max(1.0) as u64;
+    for _ in 0..frames {
         animation.update(&mut waffle);
-        
-        // Render current frame
-        renderer.render(term, &waffle)?;
-        
-        Ok(())
-    })
-    .context("Animation loop failed")?;
+        renderer.render(&mut recorder, &waffle, &animation.frame_context())?;
+    }
+    recorder.finish()?;
     
-    println!("Glowing Waffle visualization ended.");
+    println!("Recorded {} frames to {}", frames, args.output.display());
+    Ok(())
+}
+
+/// Render one frame of the animation to a file or standard output
+fn export(settings: Settings, args: &ExportArgs) -> Result<()> {
+    let (mut waffle, mut renderer, mut animation) = headless(&settings);
+    let format = args
+        .format
+        .or_else(|| args.output.as_deref().and_then(ExportFormat::from_path))
+        .unwrap_or(ExportFormat::Text);
+    
+    // Step through the animation frame by frame so it matches what `run` would show
+    let frames = (args.time.max(0.0) * settings.fps as f64).round() as u64;
+    for _ in 0..frames {
+        animation.update(&mut waffle);
+    }
+    
+    let FrameSizeArgs { width, height } = args.frame_size;
+    let mut target = HeadlessTarget::new(width, height);
+    renderer.render(&mut target, &waffle, &animation.frame_context())?;
+    
+    match &args.output {
+        Some(path) => {
+            let file = File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+            let mut out = BufWriter::new(file);
+            export::write_frame(target.frame(), format, &mut out)?;
+            out.flush()?;
+        },
+        None => {
+            let mut out = io::stdout().lock();
+            export::write_frame(target.frame(), format, &mut out)?;
+            out.flush()?;
+        },
+    }
+    Ok(())
+}
+
+/// Report the detected terminal capabilities and where settings are read from
+fn info(config_path: Option<&Path>) -> Result<()> {
+    let caps = Capabilities::detect();
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+    
+    println!("Terminal output:  {}", yes_no(caps.is_terminal));
+    match caps.size {
+        Some((columns, rows)) => println!("Size:             {}x{} cells", columns, rows),
+        None => println!("Size:             unknown"),
+    }
+    match caps.pixels {
+        Some((width, height)) => println!("Pixels:           {}x{}", width, height),
+        None => println!("Pixels:           not reported"),
+    }
+    match caps.cell_aspect {
+        Some(aspect) => println!("Cell aspect:      {:.2} (measured)", aspect),
+        None => println!("Cell aspect:      unknown, --cell-aspect is used"),
+    }
+    println!(
+        "Colors:           {} (TERM={}, COLORTERM={})",
+        caps.color_depth.name(),
+        caps.term.as_deref().unwrap_or("unset"),
+        caps.colorterm.as_deref().unwrap_or("unset"),
+    );
+    
+    let config_file = config_path
+        .map(Path::to_path_buf)
+        .or_else(|| env::var_os(config::CONFIG_ENV).map(PathBuf::from))
+        .or_else(config::default_path);
+    match config_file {
+        Some(path) if path.exists() => println!("Config file:      {}", path.display()),
+        Some(path) => println!("Config file:      {} (not found)", path.display()),
+        None => println!("Config file:      none"),
+    }
     
     Ok(())
 }
@@ -0,0 +1,323 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::waffle::Point3D;
+
+/// Positions closer than this are merged into one vertex when building a mesh
+const WELD_PRECISION: f64 = 1e-6;
+
+/// A polygon of the mesh, referencing vertices by index
+///
+/// Vertices are ordered counter-clockwise when seen from the front of the face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Face {
+    Triangle([usize; 3]),
+    Quad([usize; 4]),
+}
+
+impl Face {
+    /// Get the vertex indices of the face
+    pub fn indices(&self) -> &[usize] {
+        match self {
+            Face::Triangle(indices) => indices,
+            Face::Quad(indices) => indices,
+        }
+    }
+    
+    /// Split the face into triangles with the same winding
+    pub fn triangles(&self) -> impl Iterator<Item = [usize; 3]> {
+        let (first, second) = match *self {
+            Face::Triangle(t) => (t, None),
+            Face::Quad([a, b, c, d]) => ([a, b, c], Some([a, c, d])),
+        };
+        std::iter::once(first).chain(second)
+    }
+}
+
+/// A polygon mesh with vertices, feature edges, faces and face normals
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    vertices: Vec<Point3D>,
+    edges: Vec<[usize; 2]>,
+    faces: Vec<Face>,
+    normals: Vec<Point3D>,
+}
+
+impl Mesh {
+    /// Get the vertex positions
+    pub fn vertices(&self) -> &[Point3D] {
+        &self.vertices
+    }
+    
+    /// Get the edges to draw as lines, as pairs of vertex indices
+    pub fn edges(&self) -> &[[usize; 2]] {
+        &self.edges
+    }
+    
+    /// Get the faces
+    pub fn faces(&self) -> &[Face] {
+        &self.faces
+    }
+    
+    /// Get the outward unit normal of each face (`normals()[i]` belongs to `faces()[i]`)
+    pub fn normals(&self) -> &[Point3D] {
+        &self.normals
+    }
+    
+    /// Get the number of triangles after splitting quads
+    pub fn triangle_count(&self) -> usize {
+        self.faces.iter().map(|face| face.indices().len() - 2).sum()
+    }
+    
+    /// Radius of the smallest origin-centered sphere containing every vertex
+    pub fn bounding_radius(&self) -> f64 {
+        self.vertices.iter().map(Point3D::length).fold(0.0, f64::max)
+    }
+    
+    /// Generate a waffle: a slab with a recessed grid of square pockets on top
+    ///
+    /// The slab is centered on the origin with its top surface facing +z.
+    pub fn waffle(shape: &WaffleShape) -> Mesh {
+        let mut builder = MeshBuilder::new();
+        
+        let (hw, hh) = (shape.width / 2.0, shape.height / 2.0);
+        let bottom = -shape.thickness / 2.0;
+        let top = shape.thickness / 2.0;
+        let floor = top - shape.pocket_depth.min(shape.thickness * 0.9);
+        
+        // Bottom and sides of the slab
+        builder.quad_facing(
+            [
+                Point3D::new(-hw, -hh, bottom),
+                Point3D::new(hw, -hh, bottom),
+                Point3D::new(hw, hh, bottom),
+                Point3D::new(-hw, hh, bottom),
+            ],
+            Point3D::new(0.0, 0.0, -1.0),
+        );
+        for &(x, facing) in &[(-hw, -1.0), (hw, 1.0)] {
+            builder.quad_facing(
+                [
+                    Point3D::new(x, -hh, bottom),
+                    Point3D::new(x, hh, bottom),
+                    Point3D::new(x, hh, top),
+                    Point3D::new(x, -hh, top),
+                ],
+                Point3D::new(facing, 0.0, 0.0),
+            );
+        }
+        for &(y, facing) in &[(-hh, -1.0), (hh, 1.0)] {
+            builder.quad_facing(
+                [
+                    Point3D::new(-hw, y, bottom),
+                    Point3D::new(hw, y, bottom),
+                    Point3D::new(hw, y, top),
+                    Point3D::new(-hw, y, top),
+                ],
+                Point3D::new(0.0, facing, 0.0),
+            );
+        }
+        
+        // Outline of the slab
+        let corners = [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)];
+        builder.outline(&corners, bottom);
+        builder.outline(&corners, top);
+        for &(x, y) in &corners {
+            builder.edge(Point3D::new(x, y, bottom), Point3D::new(x, y, top));
+        }
+        
+        // Top surface: alternating ridges and pockets along each axis
+        let xs = grid_lines(shape.width, shape.pockets, shape.ridge);
+        let ys = grid_lines(shape.height, shape.pockets, shape.ridge);
+        
+        for j in 0..ys.len() - 1 {
+            for i in 0..xs.len() - 1 {
+                let (x0, x1, y0, y1) = (xs[i], xs[i + 1], ys[j], ys[j + 1]);
+                let up = Point3D::new(0.0, 0.0, 1.0);
+                
+                if i % 2 == 0 || j % 2 == 0 {
+                    // Ridge between pockets
+                    builder.quad_facing(
+                        [
+                            Point3D::new(x0, y0, top),
+                            Point3D::new(x1, y0, top),
+                            Point3D::new(x1, y1, top),
+                            Point3D::new(x0, y1, top),
+                        ],
+                        up,
+                    );
+                    continue;
+                }
+                
+                // Pocket floor
+                builder.quad_facing(
+                    [
+                        Point3D::new(x0, y0, floor),
+                        Point3D::new(x1, y0, floor),
+                        Point3D::new(x1, y1, floor),
+                        Point3D::new(x0, y1, floor),
+                    ],
+                    up,
+                );
+                
+                // Pocket walls face into the pocket
+                for &(x, facing) in &[(x0, 1.0), (x1, -1.0)] {
+                    builder.quad_facing(
+                        [
+                            Point3D::new(x, y0, floor),
+                            Point3D::new(x, y1, floor),
+                            Point3D::new(x, y1, top),
+                            Point3D::new(x, y0, top),
+                        ],
+                        Point3D::new(facing, 0.0, 0.0),
+                    );
+                }
+                for &(y, facing) in &[(y0, 1.0), (y1, -1.0)] {
+                    builder.quad_facing(
+                        [
+                            Point3D::new(x0, y, floor),
+                            Point3D::new(x1, y, floor),
+                            Point3D::new(x1, y, top),
+                            Point3D::new(x0, y, top),
+                        ],
+                        Point3D::new(0.0, facing, 0.0),
+                    );
+                }
+                
+                // Rim, floor outline and inner corners of the pocket
+                let pocket = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+                builder.outline(&pocket, top);
+                builder.outline(&pocket, floor);
+                for &(x, y) in &pocket {
+                    builder.edge(Point3D::new(x, y, floor), Point3D::new(x, y, top));
+                }
+            }
+        }
+        
+        builder.build()
+    }
+}
+
+/// Dimensions of a generated waffle slab
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaffleShape {
+    /// Extent along the x axis
+    pub width: f64,
+    /// Extent along the y axis
+    pub height: f64,
+    /// Extent along the z axis
+    pub thickness: f64,
+    /// Number of pockets along each side
+    pub pockets: usize,
+    /// Width of the ridges between and around pockets
+    pub ridge: f64,
+    /// How far the pockets are recessed into the top surface
+    pub pocket_depth: f64,
+}
+
+/// Incrementally builds a mesh, welding vertices that share a position
+pub struct MeshBuilder {
+    mesh: Mesh,
+    lookup: HashMap<(i64, i64, i64), usize>,
+    edge_set: HashSet<[usize; 2]>,
+}
+
+impl MeshBuilder {
+    pub fn new() -> Self {
+        Self {
+            mesh: Mesh::default(),
+            lookup: HashMap::new(),
+            edge_set: HashSet::new(),
+        }
+    }
+    
+    /// Add a vertex, reusing an existing one at the same position
+    pub fn vertex(&mut self, position: Point3D) -> usize {
+        let key = (
+            (position.x / WELD_PRECISION).round() as i64,
+            (position.y / WELD_PRECISION).round() as i64,
+            (position.z / WELD_PRECISION).round() as i64,
+        );
+        let vertices = &mut self.mesh.vertices;
+        *self.lookup.entry(key).or_insert_with(|| {
+            vertices.push(position);
+            vertices.len() - 1
+        })
+    }
+    
+    /// Add an edge between two positions, ignoring duplicates
+    pub fn edge(&mut self, a: Point3D, b: Point3D) {
+        let (a, b) = (self.vertex(a), self.vertex(b));
+        let key = [a.min(b), a.max(b)];
+        if a != b && self.edge_set.insert(key) {
+            self.mesh.edges.push(key);
+        }
+    }
+    
+    /// Add a closed loop of edges at a fixed height
+    fn outline(&mut self, corners: &[(f64, f64)], z: f64) {
+        for (i, &(x, y)) in corners.iter().enumerate() {
+            let (nx, ny) = corners[(i + 1) % corners.len()];
+            self.edge(Point3D::new(x, y, z), Point3D::new(nx, ny, z));
+        }
+    }
+    
+    /// Add a face, computing its normal from the winding order
+    pub fn face(&mut self, face: Face) {
+        let normal = face_normal(&self.mesh.vertices, face.indices());
+        self.mesh.faces.push(face);
+        self.mesh.normals.push(normal);
+    }
+    
+    /// Add a quad, flipping its winding if needed so the normal points along `facing`
+    pub fn quad_facing(&mut self, corners: [Point3D; 4], facing: Point3D) {
+        let mut indices = corners.map(|corner| self.vertex(corner));
+        if face_normal(&self.mesh.vertices, &indices).dot(facing) < 0.0 {
+            indices.reverse();
+        }
+        self.face(Face::Quad(indices));
+    }
+    
+    /// Finish building the mesh
+    pub fn build(self) -> Mesh {
+        self.mesh
+    }
+}
+
+impl Default for MeshBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Unit normal of a polygon using Newell's method
+fn face_normal(vertices: &[Point3D], indices: &[usize]) -> Point3D {
+    let mut normal = Point3D::new(0.0, 0.0, 0.0);
+    for (i, &index) in indices.iter().enumerate() {
+        let current = vertices[index];
+        let next = vertices[indices[(i + 1) % indices.len()]];
+        normal.x += (current.y - next.y) * (current.z + next.z);
+        normal.y += (current.z - next.z) * (current.x + next.x);
+        normal.z += (current.x - next.x) * (current.y + next.y);
+    }
+    normal.normalized()
+}
+
+/// Coordinates of the boundaries between alternating ridges and pockets
+///
+/// Produces `2 * pockets + 2` lines from `-extent / 2` to `extent / 2`, starting
+/// and ending with a ridge.
+fn grid_lines(extent: f64, pockets: usize, ridge: f64) -> Vec<f64> {
+    let pockets = pockets.max(1);
+    let ridge = ridge.min(extent / (pockets + 1) as f64 * 0.9);
+    let pocket = (extent - ridge * (pockets + 1) as f64) / pockets as f64;
+    
+    let mut lines = vec![-extent / 2.0];
+    for i in 0..2 * pockets + 1 {
+        let step = if i % 2 == 0 { ridge } else { pocket };
+        lines.push(lines[i] + step);
+    }
+    
+    // Avoid accumulated rounding at the far edge
+    lines[2 * pockets + 1] = extent / 2.0;
+    lines
+}
@@ -0,0 +1,232 @@
+use crate::waffle::Point3D;
+
+/// A vertex projected onto the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenPoint {
+    pub x: f64,
+    pub y: f64,
+    /// Larger is closer to the viewer
+    pub depth: f64,
+    /// One over the distance along the view direction under perspective, 1 otherwise
+    ///
+    /// Values are interpolated in proportion to this so that they stay correct
+    /// in 3D rather than just on the screen.
+    pub inv_w: f64,
+}
+
+impl ScreenPoint {
+    /// Create a point without perspective, where screen-space interpolation is exact
+    pub fn new(x: f64, y: f64, depth: f64) -> Self {
+        Self::with_inv_w(x, y, depth, 1.0)
+    }
+    
+    /// Create a point seen `1 / inv_w` away through a perspective camera
+    pub fn with_inv_w(x: f64, y: f64, depth: f64, inv_w: f64) -> Self {
+        Self { x, y, depth, inv_w }
+    }
+}
+
+/// What a fragment was rasterized from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+    /// Index into the mesh faces
+    Face(usize),
+    /// Index into the mesh edges
+    Edge(usize),
+    /// Index into the mesh vertices
+    Point(usize),
+}
+
+/// The closest surface covering a screen cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fragment {
+    pub depth: f64,
+    /// Interpolated position of the surface in world space
+    pub position: Point3D,
+    pub primitive: Primitive,
+}
+
+/// Per-cell depth map holding the closest fragment seen so far
+pub struct FragmentBuffer {
+    width: u16,
+    height: u16,
+    fragments: Vec<Option<Fragment>>,
+}
+
+impl FragmentBuffer {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            fragments: vec![None; width as usize * height as usize],
+        }
+    }
+    
+    /// Get the buffer width in cells
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+    
+    /// Get the buffer height in cells
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+    
+    /// Resize and clear the buffer for a new frame
+    pub fn reset(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+        self.fragments.clear();
+        self.fragments.resize(width as usize * height as usize, None);
+    }
+    
+    /// Get the fragment at a position
+    pub fn get(&self, x: u16, y: u16) -> Option<&Fragment> {
+        if x < self.width && y < self.height {
+            self.fragments[y as usize * self.width as usize + x as usize].as_ref()
+        } else {
+            None
+        }
+    }
+    
+    /// Store a fragment if it is closer than the current one (z-buffer test)
+    ///
+    /// Returns true if the fragment was written.
+    pub fn test_and_set(&mut self, x: u16, y: u16, fragment: Fragment) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        
+        let slot = &mut self.fragments[y as usize * self.width as usize + x as usize];
+        match slot {
+            Some(existing) if existing.depth >= fragment.depth => false,
+            _ => {
+                *slot = Some(fragment);
+                true
+            }
+        }
+    }
+    
+    /// Iterate over covered cells as (x, y, fragment)
+    pub fn iter(&self) -> impl Iterator<Item = (u16, u16, &Fragment)> {
+        let width = self.width.max(1) as usize;
+        self.fragments.iter().enumerate().filter_map(move |(i, fragment)| {
+            fragment.as_ref().map(|fragment| ((i % width) as u16, (i / width) as u16, fragment))
+        })
+    }
+    
+    /// Get the number of covered cells
+    pub fn coverage(&self) -> usize {
+        self.fragments.iter().filter(|fragment| fragment.is_some()).count()
+    }
+}
+
+/// Rasterize a triangle by testing the center of every cell in its bounding box
+///
+/// `visit` is called with the cell position, interpolated depth and barycentric
+/// weights of `a`, `b` and `c` for each covered cell inside `width` x `height`.
+/// The weights are perspective-correct, so they locate the point on the 3D
+/// triangle. Both windings are filled; culling is up to the caller.
+pub fn fill_triangle<F>(width: u16, height: u16, a: ScreenPoint, b: ScreenPoint, c: ScreenPoint, mut visit: F)
+where
+    F: FnMut(u16, u16, f64, [f64; 3]),
+{
+    let area = edge_function(a, b, c.x, c.y);
+    if !area.is_finite() || area.abs() < f64::EPSILON {
+        return;
+    }
+    
+    // Clamp the bounding box to the screen
+    let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as i64;
+    let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as i64;
+    let max_x = (a.x.max(b.x).max(c.x).ceil() as i64).min(width as i64 - 1);
+    let max_y = (a.y.max(b.y).max(c.y).ceil() as i64).min(height as i64 - 1);
+    
+    for y in min_y..=max_y {
+        let py = y as f64 + 0.5;
+        for x in min_x..=max_x {
+            let px = x as f64 + 0.5;
+            
+            // Normalized barycentric weights; all share the sign of the area when inside
+            let w0 = edge_function(b, c, px, py) / area;
+            let w1 = edge_function(c, a, px, py) / area;
+            let w2 = edge_function(a, b, px, py) / area;
+            
+            if w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0 {
+                let weights = perspective_correct([w0, w1, w2], [a.inv_w, b.inv_w, c.inv_w]);
+                let depth = weights[0] * a.depth + weights[1] * b.depth + weights[2] * c.depth;
+                visit(x as u16, y as u16, depth, weights);
+            }
+        }
+    }
+}
+
+/// Rasterize a line with Bresenham's algorithm
+///
+/// `visit` is called with the cell position, interpolated depth and the fraction
+/// of the way from `a` to `b` in 3D (perspective-correct) for every cell on the
+/// line inside `width` x `height`.
+pub fn draw_line<F>(width: u16, height: u16, a: ScreenPoint, b: ScreenPoint, mut visit: F)
+where
+    F: FnMut(u16, u16, f64, f64),
+{
+    // Lines this far off screen come from degenerate projections; don't walk them
+    let limit = 4.0 * (width as f64 + height as f64);
+    if [a.x, a.y, b.x, b.y].iter().any(|v| !v.is_finite() || v.abs() > limit) {
+        return;
+    }
+    
+    let (mut x, mut y) = (a.x.floor() as i64, a.y.floor() as i64);
+    let (x1, y1) = (b.x.floor() as i64, b.y.floor() as i64);
+    
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let sx = if x < x1 { 1 } else { -1 };
+    let sy = if y < y1 { 1 } else { -1 };
+    let steps = dx.max(-dy).max(1) as f64;
+    let mut err = dx + dy;
+    let mut step = 0.0;
+    
+    loop {
+        if x >= 0 && y >= 0 && x < width as i64 && y < height as i64 {
+            let [_, t] = perspective_correct([1.0 - step / steps, step / steps], [a.inv_w, b.inv_w]);
+            visit(x as u16, y as u16, a.depth + (b.depth - a.depth) * t, t);
+        }
+        
+        if x == x1 && y == y1 {
+            break;
+        }
+        
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+        step += 1.0;
+    }
+}
+
+/// Turn screen-space weights into weights of the 3D points the vertices were projected from
+fn perspective_correct<const N: usize>(weights: [f64; N], inv_w: [f64; N]) -> [f64; N] {
+    let mut corrected = weights;
+    for (weight, inv_w) in corrected.iter_mut().zip(inv_w) {
+        *weight *= inv_w;
+    }
+    
+    let total: f64 = corrected.iter().sum();
+    if total > 0.0 && total.is_finite() {
+        corrected.iter_mut().for_each(|weight| *weight /= total);
+        corrected
+    } else {
+        weights
+    }
+}
+
+/// Twice the signed area of the triangle (a, b, p)
+fn edge_function(a: ScreenPoint, b: ScreenPoint, px: f64, py: f64) -> f64 {
+    (b.x - a.x) * (py - a.y) - (b.y - a.y) * (px - a.x)
+}
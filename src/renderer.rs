@@ -1,34 +1,294 @@
-use anyhow::Result;
-use crossterm::{
-    cursor::MoveTo,
-    execute,
-    style::{Color, Print, SetForegroundColor},
+use anyhow::{anyhow, Result};
+use clap::{builder::PossibleValue, ValueEnum};
+use crossterm::style::Color;
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
 };
-use std::io;
 
-use crate::{terminal::Terminal, waffle::{Waffle, Point3D}};
+use crate::{
+    animation::FrameContext,
+    bloom::Bloom,
+    camera::{Camera, DEFAULT_CELL_ASPECT},
+    color::ColorDepth,
+    framebuffer::{Cell, FrameBuffer, RenderTarget},
+    lighting::Lighting,
+    raster::{self, Fragment, FragmentBuffer, Primitive, ScreenPoint},
+    screen::OutputStats,
+    waffle::{Waffle, Point3D},
+};
+
+/// Fraction of the screen kept free around the waffle when fitting it to the terminal
+const FIT_MARGIN: f64 = 0.1;
+
+/// Furthest the camera can zoom out relative to the fitted view
+const MIN_ZOOM: f64 = 0.25;
+
+/// Closest the camera can zoom in relative to the fitted view
+const MAX_ZOOM: f64 = 4.0;
+
+/// Brightness of edge outlines, which have no surface normal to light
+const EDGE_BRIGHTNESS: f64 = 0.9;
+
+/// How much closer edges are drawn than the faces they border, so outlines stay visible
+const EDGE_DEPTH_BIAS: f64 = 0.05;
 
 /// Available color modes for rendering
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ColorMode {
     Normal,
     Rainbow,
     Fire,
 }
 
+impl ColorMode {
+    /// Every color mode, in the order they are cycled through
+    pub const ALL: [ColorMode; 3] = [ColorMode::Normal, ColorMode::Rainbow, ColorMode::Fire];
+    
+    /// Get the name used on the command line and in config files
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorMode::Normal => "normal",
+            ColorMode::Rainbow => "rainbow",
+            ColorMode::Fire => "fire",
+        }
+    }
+    
+    /// Get a short description for listings and help
+    pub fn description(self) -> &'static str {
+        match self {
+            ColorMode::Normal => "Greyscale following the lighting",
+            ColorMode::Rainbow => "Hues swirling around the center",
+            ColorMode::Fire => "Flickering red and orange embers",
+        }
+    }
+    
+    /// Get the next color mode, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            ColorMode::Normal => ColorMode::Rainbow,
+            ColorMode::Rainbow => ColorMode::Fire,
+            ColorMode::Fire => ColorMode::Normal,
+        }
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = anyhow::Error;
+    
+    fn from_str(name: &str) -> Result<Self> {
+        let name = name.trim();
+        ColorMode::ALL
+            .into_iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<_> = ColorMode::ALL.iter().map(|mode| mode.name()).collect();
+                anyhow!("Unknown color mode '{}', expected one of: {}", name, names.join(", "))
+            })
+    }
+}
+
+impl ValueEnum for ColorMode {
+    fn value_variants<'a>() -> &'a [Self] {
+        &ColorMode::ALL
+    }
+    
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()).help(self.description()))
+    }
+}
+
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How the waffle mesh is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStyle {
+    /// Only the mesh vertices
+    Points,
+    /// Only the mesh edges, as lines
+    Wireframe,
+    /// Filled faces with the edges outlined on top
+    Solid,
+}
+
+/// Weight of the newest frame in [`TimingAverage`]
+const TIMING_SMOOTHING: f64 = 0.1;
+
+/// Step of producing a frame, in pipeline order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Rotating the model into the current orientation
+    Transform = 0,
+    /// Fitting the camera and projecting the cells onto the screen
+    Project = 1,
+    /// Filling the depth buffer with points, edges and faces
+    Rasterize = 2,
+    /// Turning fragments into lit, colored characters, with bloom and the overlay
+    Shade = 3,
+    /// Handing the frame to the target, e.g. writing it to the terminal
+    Emit = 4,
+}
+
+impl Stage {
+    /// Every stage, in pipeline order
+    pub const ALL: [Stage; 5] = [Stage::Transform, Stage::Project, Stage::Rasterize, Stage::Shade, Stage::Emit];
+    
+    /// Get the position of the stage in [`Stage::ALL`]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+    
+    /// Get the name used in reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Transform => "transform",
+            Stage::Project => "project",
+            Stage::Rasterize => "rasterize",
+            Stage::Shade => "shade",
+            Stage::Emit => "emit",
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How long each stage of the last [`Renderer::render`] call took
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameTiming {
+    /// Last rotation of the waffle, which happens when it's updated rather than during the render
+    pub transform: Duration,
+    pub project: Duration,
+    pub rasterize: Duration,
+    pub shade: Duration,
+    pub emit: Duration,
+}
+
+impl FrameTiming {
+    /// Get the time taken by one stage
+    pub fn stage(&self, stage: Stage) -> Duration {
+        match stage {
+            Stage::Transform => self.transform,
+            Stage::Project => self.project,
+            Stage::Rasterize => self.rasterize,
+            Stage::Shade => self.shade,
+            Stage::Emit => self.emit,
+        }
+    }
+    
+    /// Get the time taken drawing into the framebuffer
+    pub fn render(&self) -> Duration {
+        self.project + self.rasterize + self.shade
+    }
+    
+    /// Get the time taken by the whole frame
+    pub fn total(&self) -> Duration {
+        Stage::ALL.iter().map(|&stage| self.stage(stage)).sum()
+    }
+}
+
+/// Per-stage frame times smoothed over recent frames, for a live display
+#[derive(Debug, Clone, Default)]
+pub struct TimingAverage {
+    // Seconds per stage, indexed by `Stage::index`
+    seconds: [f64; Stage::ALL.len()],
+    frames: u64,
+}
+
+impl TimingAverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Add the timing of a frame
+    pub fn record(&mut self, timing: &FrameTiming) {
+        for (average, stage) in self.seconds.iter_mut().zip(Stage::ALL) {
+            let sample = timing.stage(stage).as_secs_f64();
+            *average = if self.frames == 0 {
+                sample
+            } else {
+                *average + (sample - *average) * TIMING_SMOOTHING
+            };
+        }
+        self.frames += 1;
+    }
+    
+    /// Get the average time of one stage
+    pub fn stage(&self, stage: Stage) -> Duration {
+        Duration::from_secs_f64(self.seconds[stage.index()])
+    }
+    
+    /// Get the number of frames recorded
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+}
+
 /// ASCII renderer for the waffle model
 pub struct Renderer {
     color_mode: ColorMode,
+    color_depth: ColorDepth,
+    style: RenderStyle,
     // Character palette from sparse to dense
     char_palette: Vec<char>,
+    lighting: Lighting,
+    bloom: Bloom,
+    camera: Camera,
+    // Cell aspect ratio used when the target can't measure its own
+    cell_aspect: f64,
+    auto_fit: bool,
+    // Zoom applied on top of the fitted camera
+    zoom: f64,
+    // Screen size, cell aspect and bounding radius the camera was last fitted to
+    fitted: Option<(u16, u16, f64, f64)>,
+    // Text drawn in a box on top of the waffle, e.g. the key binding help
+    overlay: Vec<String>,
+    timing: FrameTiming,
+    // What the target wrote for the last frame, if it encodes output
+    output: Option<OutputStats>,
+    // Frame the waffle is drawn into before being presented
+    frame: FrameBuffer,
+    // Screen positions of the waffle cells, reused between frames
+    projected: Vec<Option<ScreenPoint>>,
+    // Corners of a triangle cut by the near or far plane, reused between triangles
+    clipped: Vec<Point3D>,
+    // Closest surface per cell, resolved into characters after rasterization
+    fragments: FragmentBuffer,
 }
 
 impl Renderer {
     pub fn new(color_mode: ColorMode) -> Self {
-        // Character palette from sparse to dense for depth representation
+        // Character palette from sparse to dense for shading
         let char_palette = vec![' ', '.', ':', ';', '!', '*', 'o', '&', '%', '#', '@'];
         
-        Self { color_mode, char_palette }
+        Self {
+            color_mode,
+            color_depth: ColorDepth::TrueColor,
+            style: RenderStyle::Solid,
+            char_palette,
+            lighting: Lighting::default(),
+            bloom: Bloom::default(),
+            camera: Camera::default(),
+            cell_aspect: DEFAULT_CELL_ASPECT,
+            auto_fit: true,
+            zoom: 1.0,
+            fitted: None,
+            overlay: Vec::new(),
+            timing: FrameTiming::default(),
+            output: None,
+            frame: FrameBuffer::new(0, 0),
+            projected: Vec::new(),
+            clipped: Vec::new(),
+            fragments: FragmentBuffer::new(0, 0),
+        }
     }
     
     /// Change the color mode
@@ -36,6 +296,126 @@ impl Renderer {
         self.color_mode = mode;
     }
     
+    /// Get the color mode
+    pub fn color_mode(&self) -> ColorMode {
+        self.color_mode
+    }
+    
+    /// Limit the colors used to what the terminal can show or what is cheap to output
+    pub fn set_color_depth(&mut self, depth: ColorDepth) {
+        self.color_depth = depth;
+    }
+    
+    /// Get the color depth
+    pub fn color_depth(&self) -> ColorDepth {
+        self.color_depth
+    }
+    
+    /// Change how the mesh is drawn
+    pub fn set_style(&mut self, style: RenderStyle) {
+        self.style = style;
+    }
+    
+    /// Get how the mesh is drawn
+    pub fn style(&self) -> RenderStyle {
+        self.style
+    }
+    
+    /// Replace the lights used to shade faces
+    pub fn set_lighting(&mut self, lighting: Lighting) {
+        self.lighting = lighting;
+    }
+    
+    /// Get the lights used to shade faces
+    pub fn lighting(&self) -> &Lighting {
+        &self.lighting
+    }
+    
+    /// Replace the glow post-processing settings
+    pub fn set_bloom(&mut self, bloom: Bloom) {
+        self.bloom = bloom;
+    }
+    
+    /// Get the glow post-processing settings
+    pub fn bloom(&self) -> &Bloom {
+        &self.bloom
+    }
+    
+    /// Replace the camera the waffle is viewed through
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.camera = camera;
+        self.zoom = 1.0;
+        self.fitted = None;
+    }
+    
+    /// Get the camera the waffle is viewed through
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+    
+    /// Get mutable access to the camera
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+    
+    /// Set the cell aspect ratio (height / width) to use when the target doesn't report one
+    pub fn set_cell_aspect(&mut self, aspect: f64) {
+        self.cell_aspect = aspect;
+        self.camera.cell_aspect = aspect;
+    }
+    
+    /// Get the fallback cell aspect ratio
+    pub fn cell_aspect(&self) -> f64 {
+        self.cell_aspect
+    }
+    
+    /// Enable or disable fitting the camera to the waffle whenever the screen changes size
+    pub fn set_auto_fit(&mut self, enabled: bool) {
+        self.auto_fit = enabled;
+        self.fitted = None;
+    }
+    
+    /// Check whether the camera is fitted to the waffle automatically
+    pub fn auto_fit(&self) -> bool {
+        self.auto_fit
+    }
+    
+    /// Zoom the camera in by `factor`, keeping the zoom when the camera is refitted
+    pub fn zoom(&mut self, factor: f64) {
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.camera.zoom(zoom / self.zoom);
+        self.zoom = zoom;
+    }
+    
+    /// Get the zoom relative to the fitted camera
+    pub fn zoom_level(&self) -> f64 {
+        self.zoom
+    }
+    
+    /// Show lines of text in a box on top of the waffle; an empty list hides it
+    pub fn set_overlay(&mut self, lines: Vec<String>) {
+        self.overlay = lines;
+    }
+    
+    /// Get the lines shown on top of the waffle
+    pub fn overlay(&self) -> &[String] {
+        &self.overlay
+    }
+    
+    /// Replace the character palette, ordered from sparse to dense
+    ///
+    /// The first character is used for empty space and is never drawn for a surface.
+    pub fn set_char_palette(&mut self, palette: Vec<char>) {
+        if palette.len() >= 2 {
+            self.char_palette = palette;
+        }
+    }
+    
+    /// Get the character palette
+    pub fn char_palette(&self) -> &[char] {
+        &self.char_palette
+    }
+    
     /// Get a character based on depth value
     fn get_char_for_depth(&self, depth: f64, is_edge: bool) -> char {
         if is_edge {
@@ -51,21 +431,31 @@ impl Renderer {
         self.char_palette[char_index]
     }
     
-    /// Get a color based on depth and position
-    fn get_color(&self, point: &Point3D, depth: f64, time: f64) -> Color {
+    /// Get a character for a lit surface, brighter surfaces getting denser characters
+    fn get_char_for_luminance(&self, luminance: f64) -> char {
+        // Skip the blank so even unlit surfaces stay visible
+        let visible = self.char_palette.len() - 1;
+        let char_index = 1 + (luminance.clamp(0.0, 0.999) * visible as f64) as usize;
+        self.char_palette[char_index]
+    }
+    
+    /// Get a color based on brightness (0 to 1) and position
+    fn get_color(&self, point: &Point3D, shade: f64, time: f64) -> Color {
+        let shade = shade.clamp(0.0, 1.0);
+        
         match self.color_mode {
             ColorMode::Normal => {
-                // Simple grayscale based on depth
-                let brightness = ((depth + 2.0) / 4.0 * 200.0 + 55.0) as u8;
+                // Simple grayscale based on brightness
+                let brightness = (shade * 200.0 + 55.0) as u8;
                 Color::Rgb { r: brightness, g: brightness, b: brightness }
             },
             ColorMode::Rainbow => {
                 // Rainbow pattern that shifts with time
-                let hue = (point.x.atan2(point.y) + time) % (2.0 * std::f64::consts::PI);
+                let hue = (point.x.atan2(point.y) + time).rem_euclid(2.0 * std::f64::consts::PI);
                 let hue_norm = hue / (2.0 * std::f64::consts::PI);
                 
                 // Map hue to RGB
-                let brightness = ((depth + 2.0) / 4.0 * 155.0 + 100.0) as u8;
+                let brightness = (shade * 155.0 + 100.0) as u8;
                 
                 if hue_norm < 0.167 {
                     Color::Rgb { r: brightness, g: (brightness as f64 * (hue_norm / 0.167)).round() as u8, b: 0 }
@@ -84,7 +474,7 @@ impl Renderer {
             ColorMode::Fire => {
                 // Fire effect
                 let dist = (point.x.powi(2) + point.y.powi(2)).sqrt();
-                let glow = (dist.sin() * 0.5 + 0.5).powi(2) * ((time * 3.0).sin() * 0.3 + 0.7);
+                let glow = (dist.sin() * 0.5 + 0.5).powi(2) * ((time * 3.0).sin() * 0.3 + 0.7) * (shade * 0.5 + 0.5);
                 
                 let r = (255.0 * glow) as u8;
                 let g = (140.0 * glow * glow) as u8;
@@ -95,57 +485,270 @@ impl Renderer {
         }
     }
     
-    /// Render the waffle model
-    pub fn render(&mut self, term: &mut Terminal, waffle: &Waffle) -> Result<()> {
-        let width = term.width() as f64;
-        let height = term.height() as f64;
+    /// Get the most recently rendered frame
+    pub fn frame(&self) -> &FrameBuffer {
+        &self.frame
+    }
+    
+    /// Get how long the last call to [`Renderer::render`] took
+    pub fn timing(&self) -> FrameTiming {
+        self.timing
+    }
+    
+    /// Get how much output the target wrote for the last frame, if it encodes any
+    pub fn output_stats(&self) -> Option<OutputStats> {
+        self.output
+    }
+    
+    /// Render the waffle model and present it to a target
+    pub fn render<T: RenderTarget + ?Sized>(
+        &mut self,
+        target: &mut T,
+        waffle: &Waffle,
+        ctx: &FrameContext,
+    ) -> Result<()> {
+        let (width, height) = target.size();
         
-        // Static time for now (will be updated by animation)
-        let time = 0.0;
+        // Prefer the measured cell shape so the waffle stays round with any font
+        self.camera.cell_aspect = target.cell_aspect().unwrap_or(self.cell_aspect);
+        
+        // The waffle was rotated before this call, so take its word for how long that took
+        self.timing.transform = waffle.transform_time();
+        
+        // Draw into our own framebuffer, then hand the finished frame to the target
+        let mut frame = std::mem::replace(&mut self.frame, FrameBuffer::new(0, 0));
+        frame.resize(width, height);
+        frame.clear();
+        self.render_frame(&mut frame, waffle, ctx);
+        self.frame = frame;
+        let rendered = Instant::now();
+        
+        let result = target.present(&self.frame);
+        self.timing.emit = rendered.elapsed();
+        self.output = target.output_stats();
+        result
+    }
+    
+    /// Render the waffle model into a framebuffer
+    pub fn render_frame(&mut self, frame: &mut FrameBuffer, waffle: &Waffle, ctx: &FrameContext) {
+        let (width, height) = (frame.width(), frame.height());
+        let start = Instant::now();
         
-        // Map of characters to render
-        let mut char_map = vec![vec![' '; term.width() as usize]; term.height() as usize];
-        let mut color_map = vec![vec![Color::White; term.width() as usize]; term.height() as usize];
-        let mut depth_map = vec![vec![-1000.0; term.width() as usize]; term.height() as usize];
+        // Keep the whole waffle on screen in any orientation, refitting only when something changed
+        if self.auto_fit {
+            let key = (width, height, self.camera.cell_aspect, waffle.mesh().bounding_radius());
+            if self.fitted != Some(key) {
+                // Keep at least one free cell on tiny screens, where the outline would otherwise touch the border
+                let half_extent = (height as f64).min(width as f64 / self.camera.cell_aspect) / 2.0;
+                let margin = FIT_MARGIN.max(1.0 / half_extent.max(1.0));
+                self.camera.fit_sphere(key.3, margin, width as f64, height as f64);
+                self.camera.zoom(self.zoom);
+                self.fitted = Some(key);
+            }
+        }
         
         // Project 3D points to 2D screen coordinates
-        for cell in waffle.cells() {
-            // Simple perspective projection
-            let z_factor = (cell.point.z + 5.0) / 10.0; // Avoid division by zero
-            let screen_x = width / 2.0 + cell.point.x * 5.0 / z_factor;
-            let screen_y = height / 2.0 + cell.point.y * 2.5 / z_factor;
-            
-            // Check if the point is within screen bounds
-            if screen_x >= 0.0 && screen_x < width && screen_y >= 0.0 && screen_y < height {
-                let x = screen_x as usize;
-                let y = screen_y as usize;
-                
-                // Only draw the point if it's closer than what's already there (z-buffer)
-                if cell.depth > depth_map[y][x] {
-                    depth_map[y][x] = cell.depth;
-                    char_map[y][x] = self.get_char_for_depth(cell.depth, cell.is_edge);
-                    color_map[y][x] = self.get_color(&cell.point, cell.depth, time);
+        self.projected.clear();
+        let camera = &self.camera;
+        self.projected.extend(
+            waffle
+                .cells()
+                .iter()
+                .map(|cell| camera.project(cell.point, width as f64, height as f64)),
+        );
+        
+        let projected = Instant::now();
+        
+        // Rasterize into the depth map
+        self.fragments.reset(width, height);
+        match self.style {
+            RenderStyle::Points => self.rasterize_points(waffle),
+            RenderStyle::Wireframe => self.rasterize_edges(waffle, 0.0),
+            RenderStyle::Solid => {
+                self.rasterize_faces(waffle);
+                self.rasterize_edges(waffle, EDGE_DEPTH_BIAS);
+            }
+        }
+        
+        let rasterized = Instant::now();
+        
+        // Resolve the closest fragment of each cell into a character
+        let eye = self.camera.position;
+        for (x, y, fragment) in self.fragments.iter() {
+            let (ch, shade) = match fragment.primitive {
+                Primitive::Face(face) => {
+                    let luminance = self.lighting.luminance(fragment.position, waffle.normals()[face], eye);
+                    (self.get_char_for_luminance(luminance), luminance)
                 }
+                Primitive::Edge(_) => (self.get_char_for_depth(fragment.depth, true), EDGE_BRIGHTNESS),
+                Primitive::Point(id) => (
+                    self.get_char_for_depth(fragment.depth, waffle.cells()[id].is_edge),
+                    (fragment.depth + 2.0) / 4.0,
+                ),
+            };
+            
+            frame.plot(x, y, Cell::new(ch, self.get_color(&fragment.position, shade, ctx.time), fragment.depth));
+        }
+        
+        // Let bright surfaces glow into the space around them
+        self.bloom.apply(frame);
+        
+        if self.color_depth != ColorDepth::TrueColor {
+            for cell in frame.cells_mut() {
+                cell.color = self.color_depth.quantize(cell.color);
             }
         }
         
-        // Render the character map to the terminal
-        let stdout = io::stdout();
-        let mut stdout = stdout.lock();
+        self.draw_overlay(frame);
+        
+        self.timing.project = projected - start;
+        self.timing.rasterize = rasterized - projected;
+        self.timing.shade = rasterized.elapsed();
+    }
+    
+    /// Draw the overlay text in a bordered box in the top-left corner
+    fn draw_overlay(&self, frame: &mut FrameBuffer) {
+        if self.overlay.is_empty() {
+            return;
+        }
+        
+        let inner = self.overlay.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        let (left, top) = (1usize, 1usize);
+        let (right, bottom) = (left + inner + 3, top + self.overlay.len() + 1);
         
-        for y in 0..term.height() as usize {
-            for x in 0..term.width() as usize {
-                if char_map[y][x] != ' ' {
-                    execute!(
-                        stdout,
-                        MoveTo(x as u16, y as u16),
-                        SetForegroundColor(color_map[y][x]),
-                        Print(char_map[y][x])
-                    )?;
+        for y in top..=bottom {
+            let line = y.checked_sub(top + 1).and_then(|i| self.overlay.get(i));
+            let mut text = line.into_iter().flat_map(|line| line.chars());
+            for x in left..=right {
+                let ch = match (x, y) {
+                    (x, y) if (x == left || x == right) && (y == top || y == bottom) => '+',
+                    (_, y) if y == top || y == bottom => '-',
+                    (x, _) if x == left || x == right => '|',
+                    (x, _) if x < left + 2 => ' ',
+                    _ => text.next().unwrap_or(' '),
+                };
+                if x <= u16::MAX as usize && y <= u16::MAX as usize {
+                    frame.set(x as u16, y as u16, Cell::new(ch, Color::White, f64::INFINITY));
+                }
+            }
+        }
+    }
+    
+    /// Draw every vertex as a single cell
+    fn rasterize_points(&mut self, waffle: &Waffle) {
+        for cell in waffle.sorted_cells() {
+            if let Some(p) = self.projected[cell.id] {
+                if p.x >= 0.0 && p.y >= 0.0 && p.x < u16::MAX as f64 && p.y < u16::MAX as f64 {
+                    self.fragments.test_and_set(
+                        p.x as u16,
+                        p.y as u16,
+                        Fragment {
+                            depth: p.depth,
+                            position: cell.point,
+                            primitive: Primitive::Point(cell.id),
+                        },
+                    );
                 }
             }
         }
+    }
+    
+    /// Draw the mesh edges as lines
+    fn rasterize_edges(&mut self, waffle: &Waffle, depth_bias: f64) {
+        let (width, height) = (self.fragments.width(), self.fragments.height());
+        let cells = waffle.cells();
         
-        Ok(())
+        for (i, &[a, b]) in waffle.mesh().edges().iter().enumerate() {
+            let (mut start, mut end) = (cells[a].point, cells[b].point);
+            let (pa, pb) = match (self.projected[a], self.projected[b]) {
+                (Some(pa), Some(pb)) => (pa, pb),
+                _ => {
+                    // Part of the edge is behind the near plane or beyond the far plane
+                    let Some((from, to)) = self.camera.clip_line(start, end) else {
+                        continue;
+                    };
+                    (start, end) = (start + (end - start) * from, start + (end - start) * to);
+                    let project = |point| self.camera.project(point, width as f64, height as f64);
+                    let (Some(pa), Some(pb)) = (project(start), project(end)) else {
+                        continue;
+                    };
+                    (pa, pb)
+                },
+            };
+            
+            let fragments = &mut self.fragments;
+            raster::draw_line(width, height, pa, pb, |x, y, depth, t| {
+                fragments.test_and_set(
+                    x,
+                    y,
+                    Fragment {
+                        depth: depth + depth_bias,
+                        position: start + (end - start) * t,
+                        primitive: Primitive::Edge(i),
+                    },
+                );
+            });
+        }
     }
+    
+    /// Fill the mesh faces that face the viewer
+    fn rasterize_faces(&mut self, waffle: &Waffle) {
+        let (width, height) = (self.fragments.width(), self.fragments.height());
+        let cells = waffle.cells();
+        
+        for (i, (face, normal)) in waffle.mesh().faces().iter().zip(waffle.normals()).enumerate() {
+            // Back-face culling: skip faces pointing away from the viewer
+            let corner = cells[face.indices()[0]].point;
+            if !self.camera.is_facing(corner, *normal) {
+                continue;
+            }
+            
+            for [a, b, c] in face.triangles() {
+                let corners = [cells[a].point, cells[b].point, cells[c].point];
+                if let (Some(pa), Some(pb), Some(pc)) = (self.projected[a], self.projected[b], self.projected[c]) {
+                    fill_face(&mut self.fragments, i, [pa, pb, pc], corners);
+                    continue;
+                }
+                
+                // Part of the triangle is behind the near plane or beyond the far plane,
+                // so fill what is left of it as a fan
+                self.camera.clip_polygon(&corners, &mut self.clipped);
+                let projected: Option<Vec<_>> = self
+                    .clipped
+                    .iter()
+                    .map(|&point| self.camera.project(point, width as f64, height as f64))
+                    .collect();
+                let Some(projected) = projected else {
+                    continue;
+                };
+                
+                for j in 1..projected.len().saturating_sub(1) {
+                    fill_face(
+                        &mut self.fragments,
+                        i,
+                        [projected[0], projected[j], projected[j + 1]],
+                        [self.clipped[0], self.clipped[j], self.clipped[j + 1]],
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Fill a triangle of face `face`, whose corners are at `points` in world space
+fn fill_face(fragments: &mut FragmentBuffer, face: usize, projected: [ScreenPoint; 3], points: [Point3D; 3]) {
+    let (width, height) = (fragments.width(), fragments.height());
+    let [pa, pb, pc] = projected;
+    let [va, vb, vc] = points;
+    raster::fill_triangle(width, height, pa, pb, pc, |x, y, depth, [wa, wb, wc]| {
+        fragments.test_and_set(
+            x,
+            y,
+            Fragment {
+                depth,
+                position: va * wa + vb * wb + vc * wc,
+                primitive: Primitive::Face(face),
+            },
+        );
+    });
 }
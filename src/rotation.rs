@@ -0,0 +1,99 @@
+use crate::waffle::Point3D;
+
+/// An orientation in 3D space, stored as a unit quaternion
+///
+/// Unlike Euler angles, rotations compose in any order without gimbal lock,
+/// so the waffle can be turned freely with the mouse. Angles follow the right
+/// hand rule: a positive angle turns counter-clockwise when the axis points
+/// at the viewer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rotation {
+    w: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Rotation {
+    /// The rotation that leaves everything where it is
+    pub const IDENTITY: Rotation = Rotation { w: 1.0, x: 0.0, y: 0.0, z: 0.0 };
+    
+    /// Turn by `angle` radians around `axis` (a zero axis turns nothing)
+    pub fn from_axis_angle(axis: Point3D, angle: f64) -> Self {
+        let axis = axis.normalized();
+        if axis.length() == 0.0 {
+            return Self::IDENTITY;
+        }
+        
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Self {
+            w: cos,
+            x: axis.x * sin,
+            y: axis.y * sin,
+            z: axis.z * sin,
+        }
+    }
+    
+    /// Turn around the direction of `vector` by its length in radians
+    pub fn from_vector(vector: Point3D) -> Self {
+        Self::from_axis_angle(vector, vector.length())
+    }
+    
+    /// Turn by the same angles as [`Point3D::rotate_x`], then `rotate_y`, then `rotate_z`
+    pub fn from_euler(x: f64, y: f64, z: f64) -> Self {
+        // rotate_y turns the other way from the right hand rule
+        Self::from_axis_angle(Point3D::new(1.0, 0.0, 0.0), x)
+            .then(Self::from_axis_angle(Point3D::new(0.0, 1.0, 0.0), -y))
+            .then(Self::from_axis_angle(Point3D::new(0.0, 0.0, 1.0), z))
+    }
+    
+    /// Apply this rotation and then `next`
+    pub fn then(self, next: Rotation) -> Rotation {
+        let (a, b) = (next, self);
+        let product = Rotation {
+            w: a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+            x: a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            y: a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            z: a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        };
+        
+        // Keep rounding errors from building up over many small turns
+        let Rotation { w, x, y, z } = product;
+        let length = (w * w + x * x + y * y + z * z).sqrt();
+        Rotation {
+            w: w / length,
+            x: x / length,
+            y: y / length,
+            z: z / length,
+        }
+    }
+    
+    /// Get the angle turned in radians, between 0 and pi
+    pub fn angle(&self) -> f64 {
+        let sin = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        2.0 * sin.atan2(self.w.abs())
+    }
+    
+    /// Get the unit axis turned around (zero for the identity)
+    pub fn axis(&self) -> Point3D {
+        let axis = Point3D::new(self.x, self.y, self.z).normalized();
+        if self.w < 0.0 {
+            -axis
+        } else {
+            axis
+        }
+    }
+    
+    /// Rotate a point around the origin
+    pub fn apply(&self, point: Point3D) -> Point3D {
+        let axis = Point3D::new(self.x, self.y, self.z);
+        let t = axis.cross(point) * 2.0;
+        point + t * self.w + axis.cross(t)
+    }
+}
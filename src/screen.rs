@@ -0,0 +1,187 @@
+use anyhow::Result;
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{Color, Print, SetForegroundColor},
+    terminal::{Clear, ClearType},
+};
+use std::io::{self, Write};
+
+use crate::framebuffer::{Cell, FrameBuffer};
+
+/// Longest run of unchanged cells that is reprinted instead of moving the cursor
+const MAX_BRIDGE_GAP: usize = 4;
+
+/// What writing one frame sent to the terminal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputStats {
+    /// Bytes written, escape sequences included
+    pub bytes: u64,
+    /// Cursor moves, color changes and clears
+    pub escapes: u64,
+    /// Cells that look different from the previous frame
+    pub cells_changed: u64,
+}
+
+/// Double-buffered screen that only emits the cells that changed since the last frame
+///
+/// The front buffer mirrors what is currently on the terminal. Each new (back) frame
+/// is diffed against it and only the differences are queued to the writer.
+pub struct ScreenBuffer {
+    front: FrameBuffer,
+    color: Option<Color>,
+    cursor: Option<(u16, u16)>,
+    needs_redraw: bool,
+    last_output: OutputStats,
+}
+
+impl ScreenBuffer {
+    pub fn new() -> Self {
+        Self {
+            front: FrameBuffer::new(0, 0),
+            color: None,
+            cursor: None,
+            needs_redraw: true,
+            last_output: OutputStats::default(),
+        }
+    }
+    
+    /// Forget what is on screen so the next frame is drawn in full
+    ///
+    /// Call this after anything else has written to or cleared the terminal.
+    pub fn invalidate(&mut self) {
+        self.needs_redraw = true;
+    }
+    
+    /// Get the frame that is currently on screen
+    pub fn front(&self) -> &FrameBuffer {
+        &self.front
+    }
+    
+    /// Get what the last call to [`ScreenBuffer::write_frame`] wrote
+    pub fn last_output(&self) -> OutputStats {
+        self.last_output
+    }
+    
+    /// Queue the escape sequences that turn the front buffer into `back`
+    ///
+    /// Nothing is flushed; the caller decides when the frame goes out.
+    pub fn write_frame<W: Write>(&mut self, back: &FrameBuffer, out: &mut W) -> Result<()> {
+        let mut out = CountingWriter { inner: out, bytes: 0 };
+        let mut stats = OutputStats::default();
+        
+        if self.needs_redraw || self.front.width() != back.width() || self.front.height() != back.height() {
+            queue!(out, Clear(ClearType::All))?;
+            stats.escapes += 1;
+            self.front = FrameBuffer::new(back.width(), back.height());
+            self.color = None;
+            self.cursor = None;
+            self.needs_redraw = false;
+        }
+        
+        let width = back.width() as usize;
+        let mut run = String::new();
+        
+        for (y, (new_row, old_row)) in back.rows().zip(self.front.rows()).enumerate() {
+            let mut x = 0;
+            
+            while x < width {
+                if same_glyph(&new_row[x], &old_row[x]) {
+                    x += 1;
+                    continue;
+                }
+                
+                // Start of a run of changed cells
+                if self.cursor != Some((x as u16, y as u16)) {
+                    queue!(out, MoveTo(x as u16, y as u16))?;
+                    stats.escapes += 1;
+                }
+                
+                let start = x;
+                let mut end = x;
+                while end < width {
+                    if !same_glyph(&new_row[end], &old_row[end]) {
+                        end += 1;
+                        continue;
+                    }
+                    
+                    // Bridge short gaps of unchanged cells when reprinting them is cheaper
+                    // than moving the cursor and they don't force a color change
+                    let gap_end = (end..width)
+                        .take(MAX_BRIDGE_GAP + 1)
+                        .find(|&i| !same_glyph(&new_row[i], &old_row[i]));
+                    let run_color = new_row[end - 1].color;
+                    match gap_end {
+                        Some(next)
+                            if new_row[end..next]
+                                .iter()
+                                .all(|cell| cell.is_empty() || cell.color == run_color) =>
+                        {
+                            end = next;
+                        }
+                        _ => break,
+                    }
+                }
+                
+                for (cell, old) in new_row[start..end].iter().zip(&old_row[start..end]) {
+                    if !cell.is_empty() && self.color != Some(cell.color) {
+                        if !run.is_empty() {
+                            queue!(out, Print(&run))?;
+                            run.clear();
+                        }
+                        queue!(out, SetForegroundColor(cell.color))?;
+                        self.color = Some(cell.color);
+                        stats.escapes += 1;
+                    }
+                    if !same_glyph(cell, old) {
+                        stats.cells_changed += 1;
+                    }
+                    run.push(cell.ch);
+                }
+                
+                if !run.is_empty() {
+                    queue!(out, Print(&run))?;
+                    run.clear();
+                }
+                
+                self.cursor = Some((end as u16, y as u16));
+                x = end;
+            }
+        }
+        
+        self.front.clone_from(back);
+        
+        stats.bytes = out.bytes;
+        self.last_output = stats;
+        Ok(())
+    }
+}
+
+impl Default for ScreenBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writer that counts the bytes passing through it
+struct CountingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    bytes: u64,
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written as u64;
+        Ok(written)
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Check if two cells look the same on screen
+fn same_glyph(a: &Cell, b: &Cell) -> bool {
+    a.ch == b.ch && (a.is_empty() || a.color == b.color)
+}
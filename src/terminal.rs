@@ -1,26 +1,112 @@
 use anyhow::{Context, Result};
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{
+        DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture, KeyCode, KeyEventKind,
+        KeyModifiers,
+    },
     execute,
     terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
     cursor::{Hide, Show},
 };
 use std::{
-    io::{self, Write},
-    time::{Duration, Instant},
+    env,
+    io::{self, IsTerminal, Write},
+    time::Duration,
 };
 
+use crate::{
+    color::ColorDepth,
+    event_loop::{self, AppEvent, Control, EventHandler, TerminalEvents},
+    framebuffer::{FrameBuffer, RenderTarget},
+    screen::{OutputStats, ScreenBuffer},
+};
+
+/// Cell aspect ratios outside this range are treated as bogus pixel reports
+const CELL_ASPECT_RANGE: (f64, f64) = (0.5, 4.0);
+
+/// Compute the height-to-width ratio of a character cell from the window size
+///
+/// Returns `None` when the pixel size is unknown (many terminals report zero)
+/// or implausible.
+pub fn cell_aspect_from_pixels(columns: u16, rows: u16, width: u16, height: u16) -> Option<f64> {
+    if columns == 0 || rows == 0 || width == 0 || height == 0 {
+        return None;
+    }
+    
+    let cell_width = width as f64 / columns as f64;
+    let cell_height = height as f64 / rows as f64;
+    let aspect = cell_height / cell_width;
+    
+    let (min, max) = CELL_ASPECT_RANGE;
+    (min..=max).contains(&aspect).then_some(aspect)
+}
+
+/// Ask the terminal for its pixel size and derive the cell aspect ratio
+fn detect_cell_aspect() -> Option<f64> {
+    let size = terminal::window_size().ok()?;
+    cell_aspect_from_pixels(size.columns, size.rows, size.width, size.height)
+}
+
+/// What could be found out about the terminal without taking it over
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    /// Whether standard output is a terminal at all
+    pub is_terminal: bool,
+    /// Size in cells as (columns, rows)
+    pub size: Option<(u16, u16)>,
+    /// Size in pixels as (width, height), which many terminals don't report
+    pub pixels: Option<(u16, u16)>,
+    /// Cell height divided by width, measured from the pixel size
+    pub cell_aspect: Option<f64>,
+    /// Colors supported according to the environment
+    pub color_depth: ColorDepth,
+    /// The `TERM` variable
+    pub term: Option<String>,
+    /// The `COLORTERM` variable
+    pub colorterm: Option<String>,
+}
+
+impl Capabilities {
+    /// Query the terminal and the environment
+    pub fn detect() -> Self {
+        let window = terminal::window_size().ok();
+        let term = env::var("TERM").ok();
+        let colorterm = env::var("COLORTERM").ok();
+        Self {
+            is_terminal: io::stdout().is_terminal(),
+            size: terminal::size().ok(),
+            pixels: window
+                .as_ref()
+                .filter(|size| size.width > 0 && size.height > 0)
+                .map(|size| (size.width, size.height)),
+            cell_aspect: detect_cell_aspect(),
+            color_depth: ColorDepth::detect(colorterm.as_deref(), term.as_deref()),
+            term,
+            colorterm,
+        }
+    }
+}
+
 /// Terminal handler for managing the terminal state and user input
 pub struct Terminal {
     width: u16,
     height: u16,
+    // Measured from the pixel size when the terminal reports it
+    cell_aspect: Option<f64>,
+    screen: ScreenBuffer,
+    // Escape sequences for the current frame, written out in one go
+    output: Vec<u8>,
+    // Time between frames of the running event loop
+    frame_duration: Duration,
+    mouse_capture: bool,
+    quit_requested: bool,
 }
 
 impl Terminal {
     /// Create a new terminal instance and initialize the terminal
     pub fn new() -> Result<Self> {
-        // Enter alternate screen
-        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        // Enter alternate screen and report focus changes
+        execute!(io::stdout(), EnterAlternateScreen, Hide, EnableFocusChange)?;
         
         // Enable raw mode
         terminal::enable_raw_mode().context("Failed to enable raw mode")?;
@@ -28,7 +114,16 @@ impl Terminal {
         // Get terminal size
         let (width, height) = terminal::size().context("Failed to get terminal size")?;
         
-        Ok(Self { width, height })
+        Ok(Self {
+            width,
+            height,
+            cell_aspect: detect_cell_aspect(),
+            screen: ScreenBuffer::new(),
+            output: Vec::new(),
+            frame_duration: Duration::from_millis(1000 / 30),
+            mouse_capture: false,
+            quit_requested: false,
+        })
     }
     
     /// Get terminal width
@@ -43,66 +138,128 @@ impl Terminal {
     
     /// Clear the terminal screen
     pub fn clear(&mut self) -> Result<()> {
+        // Whatever was on screen is gone, so the next frame must be drawn in full
+        self.screen.invalidate();
         execute!(io::stdout(), Clear(ClearType::All)).context("Failed to clear terminal")
     }
     
+    /// Start or stop receiving mouse events as [`AppEvent::Mouse`]
+    ///
+    /// While enabled, the terminal's own text selection usually needs Shift held.
+    pub fn set_mouse_capture(&mut self, capture: bool) -> Result<()> {
+        if capture != self.mouse_capture {
+            if capture {
+                execute!(io::stdout(), EnableMouseCapture).context("Failed to enable mouse capture")?;
+            } else {
+                execute!(io::stdout(), DisableMouseCapture).context("Failed to disable mouse capture")?;
+            }
+            self.mouse_capture = capture;
+        }
+        Ok(())
+    }
+    
+    /// End the event loop once the current frame is done
+    pub fn request_quit(&mut self) {
+        self.quit_requested = true;
+    }
+    
+    /// Change the time between frames of the running event loop
+    pub fn set_frame_duration(&mut self, frame_duration: Duration) {
+        self.frame_duration = frame_duration;
+    }
+    
+    /// Get the time between frames
+    pub fn frame_duration(&self) -> Duration {
+        self.frame_duration
+    }
+    
+    /// Run the event loop, dispatching input, resize, focus and frame ticks to `handler`
+    pub fn run<H: EventHandler<Self>>(&mut self, frame_duration: Duration, handler: &mut H) -> Result<()> {
+        self.frame_duration = frame_duration;
+        event_loop::run(self, &mut TerminalEvents, handler, frame_duration)
+    }
+    
     /// Run the main event loop with a callback for each frame
+    ///
+    /// Quits on 'q' or Ctrl+C, or when the callback calls [`Terminal::request_quit`].
     pub fn run_event_loop<F>(&mut self, frame_duration: Duration, mut callback: F) -> Result<()>
     where
         F: FnMut(&mut Self) -> Result<()>,
     {
-        let mut last_frame = Instant::now();
-        
-        loop {
-            // Handle resize events
-            if event::poll(Duration::from_millis(0))? {
-                if let Event::Resize(width, height) = event::read()? {
-                    self.width = width;
-                    self.height = height;
-                }
-            }
-            
-            // Check if it's time for a new frame
-            let current = Instant::now();
-            let elapsed = current.duration_since(last_frame);
-            
-            if elapsed >= frame_duration {
-                // Clear screen for new frame
-                self.clear()?;
-                
-                // Run the frame callback
-                callback(self)?;
-                
-                // Flush stdout
-                io::stdout().flush()?;
-                
-                // Update last frame time
-                last_frame = current;
-            }
-            
-            // Check for user input with a small timeout
-            if event::poll(Duration::from_millis(10))? {
-                if let Event::Key(KeyEvent { code, modifiers, .. }) = event::read()? {
-                    match code {
-                        // Quit on 'q' or Ctrl+C
-                        KeyCode::Char('q') | KeyCode::Char('Q') => break,
-                        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
-                        _ => {}
+        self.quit_requested = false;
+        let mut handler = |event: AppEvent, term: &mut Self| -> Result<Control> {
+            match event {
+                AppEvent::Tick => {
+                    let pace = term.frame_duration;
+                    callback(term)?;
+                    
+                    if term.quit_requested {
+                        return Ok(Control::Quit);
+                    }
+                    if term.frame_duration != pace {
+                        return Ok(Control::SetFrameDuration(term.frame_duration));
                     }
-                }
+                },
+                AppEvent::Key(key) if key.kind != KeyEventKind::Release => match key.code {
+                    // Quit on 'q' or Ctrl+C
+                    KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(Control::Quit),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(Control::Quit),
+                    _ => {}
+                },
+                _ => {}
             }
-        }
+            Ok(Control::Continue)
+        };
+        
+        self.run(frame_duration, &mut handler)
+    }
+}
+
+impl RenderTarget for Terminal {
+    fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+    
+    fn cell_aspect(&self) -> Option<f64> {
+        self.cell_aspect
+    }
+    
+    fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+        // The font may have changed too (e.g. zooming), so measure again
+        self.cell_aspect = detect_cell_aspect();
+        self.screen.invalidate();
+    }
+    
+    fn present(&mut self, frame: &FrameBuffer) -> Result<()> {
+        // Only emit what changed since the last frame and flush it as a single write
+        self.output.clear();
+        self.screen.write_frame(frame, &mut self.output)?;
+        
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        stdout.write_all(&self.output)?;
+        stdout.flush()?;
         
         Ok(())
     }
+    
+    fn output_stats(&self) -> Option<OutputStats> {
+        Some(self.screen.last_output())
+    }
 }
 
 impl Drop for Terminal {
     fn drop(&mut self) {
+        if self.mouse_capture {
+            let _ = execute!(io::stdout(), DisableMouseCapture);
+        }
+        
         // Disable raw mode
         let _ = terminal::disable_raw_mode();
         
         // Leave alternate screen and show cursor
-        let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+        let _ = execute!(io::stdout(), DisableFocusChange, LeaveAlternateScreen, Show);
     }
 }
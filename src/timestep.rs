@@ -0,0 +1,144 @@
+use std::{collections::VecDeque, time::Duration};
+
+/// Default number of simulation updates per second
+pub const DEFAULT_UPDATE_RATE: u32 = 60;
+
+/// Longest stretch of time one frame catches up on before the rest is dropped
+const MAX_CATCH_UP: Duration = Duration::from_millis(250);
+
+/// How much history the frame rate is averaged over
+const STATS_WINDOW: Duration = Duration::from_secs(1);
+
+/// The updates to run for one rendered frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Steps {
+    /// Number of fixed steps to simulate
+    pub count: u32,
+    /// How far the leftover time is into the next step, from 0 to 1
+    pub alpha: f64,
+    /// Steps that were dropped because the frame was too late to catch up
+    pub skipped: u32,
+}
+
+/// Splits elapsed time into fixed simulation steps
+///
+/// Time is collected in an accumulator and handed out in steps of the same
+/// length, so the simulation behaves identically no matter how often frames are
+/// drawn. The part of a step left over is reported as `alpha` for interpolating
+/// between the previous and current state.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+    max_steps: u32,
+    total_steps: u64,
+    total_skipped: u64,
+}
+
+impl FixedTimestep {
+    pub fn new(step: Duration) -> Self {
+        let step = step.max(Duration::from_micros(100));
+        Self {
+            step,
+            accumulator: Duration::ZERO,
+            max_steps: (MAX_CATCH_UP.as_nanos() / step.as_nanos()).max(1) as u32,
+            total_steps: 0,
+            total_skipped: 0,
+        }
+    }
+    
+    /// Create a timestep running `rate` updates per second
+    pub fn from_rate(rate: u32) -> Self {
+        Self::new(Duration::from_secs_f64(1.0 / rate.max(1) as f64))
+    }
+    
+    /// Get the length of one step
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+    
+    /// Get the number of updates per second
+    pub fn rate(&self) -> f64 {
+        1.0 / self.step.as_secs_f64()
+    }
+    
+    /// Set how many steps one frame may run before falling behind is accepted
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps.max(1);
+    }
+    
+    /// Add the time since the last frame and get the steps to run for it
+    pub fn accumulate(&mut self, dt: Duration) -> Steps {
+        self.accumulator += dt;
+        
+        let due = (self.accumulator.as_nanos() / self.step.as_nanos()) as u64;
+        let count = due.min(self.max_steps as u64) as u32;
+        let skipped = (due - count as u64) as u32;
+        
+        // Drop whole steps we can't catch up on, but keep the fraction for interpolation
+        self.accumulator -= self.step * (count + skipped);
+        self.total_steps += count as u64;
+        self.total_skipped += skipped as u64;
+        
+        Steps {
+            count,
+            alpha: self.accumulator.as_secs_f64() / self.step.as_secs_f64(),
+            skipped,
+        }
+    }
+    
+    /// Forget any accumulated time, e.g. after a pause
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+    }
+    
+    /// Get the number of steps run so far
+    pub fn total_steps(&self) -> u64 {
+        self.total_steps
+    }
+    
+    /// Get the number of steps dropped so far
+    pub fn total_skipped(&self) -> u64 {
+        self.total_skipped
+    }
+}
+
+/// Measures the frame rate actually achieved
+#[derive(Debug, Clone, Default)]
+pub struct FrameStats {
+    // Time between consecutive frames, newest last, covering about `STATS_WINDOW`
+    intervals: VecDeque<Duration>,
+    total: Duration,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Record that a frame was drawn `interval` after the previous one
+    pub fn record_frame(&mut self, interval: Duration) {
+        self.intervals.push_back(interval);
+        self.total += interval;
+        
+        // Keep at least one interval so slow frame rates still report something
+        while self.intervals.len() > 1 && self.total - self.intervals[0] >= STATS_WINDOW {
+            let oldest = self.intervals.pop_front().unwrap_or_default();
+            self.total -= oldest;
+        }
+    }
+    
+    /// Get the average frames per second over the last second
+    pub fn fps(&self) -> f64 {
+        if self.total.is_zero() {
+            0.0
+        } else {
+            self.intervals.len() as f64 / self.total.as_secs_f64()
+        }
+    }
+    
+    /// Get the slowest frame interval in the window
+    pub fn worst_interval(&self) -> Duration {
+        self.intervals.iter().copied().max().unwrap_or_default()
+    }
+}
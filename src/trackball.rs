@@ -0,0 +1,139 @@
+use std::{f64::consts::PI, time::Duration};
+
+use crate::{
+    camera::Camera,
+    rotation::Rotation,
+    waffle::{Point3D, Waffle},
+};
+
+/// How quickly a released spin slows down, per second
+const FRICTION: f64 = 2.5;
+
+/// Spins slower than this (radians per second) stop and hand control back
+const MIN_SPIN: f64 = 0.05;
+
+/// How much of the newest drag speed is blended into the spin each frame
+const VELOCITY_SMOOTHING: f64 = 0.5;
+
+/// Lets the user grab and spin the waffle with the mouse
+///
+/// Dragging across the full height of the screen turns the waffle half way
+/// around, like rolling a ball under the pointer: it turns around the screen
+/// axis at right angles to the drag, whichever way it currently faces. When
+/// released, the waffle keeps spinning and slowly comes to rest, after which
+/// the animation takes over again.
+#[derive(Debug, Clone, Default)]
+pub struct Trackball {
+    state: State,
+    // Pointer position of the last drag event in cells
+    last: (u16, u16),
+    // Rotation applied by drag events since the last frame, as axis times angle
+    moved: Point3D,
+    // Spin axis in world space times radians per second
+    velocity: Point3D,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Idle,
+    Dragging,
+    Coasting,
+}
+
+impl Trackball {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Check whether the user is controlling the waffle, by dragging or a spin that hasn't settled
+    pub fn is_active(&self) -> bool {
+        self.state != State::Idle
+    }
+    
+    /// Check whether the mouse button is held
+    pub fn is_dragging(&self) -> bool {
+        self.state == State::Dragging
+    }
+    
+    /// Get the current spin: its axis in world space times radians per second
+    pub fn velocity(&self) -> Point3D {
+        self.velocity
+    }
+    
+    /// Grab the waffle at a screen cell, stopping any spin
+    pub fn press(&mut self, column: u16, row: u16) {
+        self.state = State::Dragging;
+        self.last = (column, row);
+        self.moved = Point3D::default();
+        self.velocity = Point3D::default();
+    }
+    
+    /// Turn the waffle to follow the pointer
+    ///
+    /// `camera` is the one the waffle is seen through, so the waffle turns around
+    /// axes on the screen wherever the camera is. `height` is the screen height in
+    /// rows; the camera's cell aspect makes horizontal and vertical drags of the
+    /// same physical length turn the waffle equally far.
+    pub fn drag(&mut self, waffle: &mut Waffle, camera: &Camera, column: u16, row: u16, height: u16) {
+        if self.state != State::Dragging {
+            self.press(column, row);
+            return;
+        }
+        
+        let per_row = PI / height.max(1) as f64;
+        let dx = (column as f64 - self.last.0 as f64) / camera.cell_aspect * per_row;
+        let dy = (row as f64 - self.last.1 as f64) * per_row;
+        self.last = (column, row);
+        
+        // Roll around the axis at right angles to both the drag and the view,
+        // so dragging down tips the top towards the viewer (rows count downwards)
+        let (right, up, forward) = camera.basis();
+        let spin = (-forward).cross(right * dx - up * dy);
+        waffle.turn(Rotation::from_vector(spin));
+        self.moved = self.moved + spin;
+    }
+    
+    /// Let go of the waffle, leaving it spinning at the speed it was dragged
+    pub fn release(&mut self) {
+        if self.state == State::Dragging {
+            self.state = State::Coasting;
+            self.settle();
+        }
+    }
+    
+    /// Stop any spin and hand control back to the animation
+    pub fn stop(&mut self) {
+        self.state = State::Idle;
+        self.moved = Point3D::default();
+        self.velocity = Point3D::default();
+    }
+    
+    /// Advance by one frame, spinning the waffle after it has been released
+    pub fn update(&mut self, waffle: &mut Waffle, dt: Duration) {
+        let dt = dt.as_secs_f64();
+        match self.state {
+            State::Idle => {},
+            State::Dragging => {
+                // Track how fast the pointer moves so the spin matches it on release
+                if dt > 0.0 {
+                    let speed = self.moved * (1.0 / dt);
+                    self.velocity = self.velocity + (speed - self.velocity) * VELOCITY_SMOOTHING;
+                }
+                self.moved = Point3D::default();
+            },
+            State::Coasting => {
+                waffle.turn(Rotation::from_vector(self.velocity * dt));
+                self.velocity = self.velocity * (-FRICTION * dt).exp();
+                self.settle();
+            },
+        }
+    }
+    
+    /// Hand control back once a released spin has slowed down enough
+    fn settle(&mut self) {
+        if self.state == State::Coasting && self.velocity.length() < MIN_SPIN {
+            self.stop();
+        }
+    }
+}
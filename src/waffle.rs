@@ -1,15 +1,102 @@
-use std::f64::consts::PI;
+use anyhow::{anyhow, Result};
+use clap::{builder::PossibleValue, ValueEnum};
+use std::{
+    f64::consts::PI,
+    fmt,
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    mesh::{Mesh, WaffleShape},
+    rotation::Rotation,
+};
 
 /// Size options for the waffle
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Size {
     Small,
     Medium,
     Large,
 }
 
+impl Size {
+    /// Every size, from smallest to largest
+    pub const ALL: [Size; 3] = [Size::Small, Size::Medium, Size::Large];
+    
+    /// Get the name used on the command line and in config files
+    pub fn name(self) -> &'static str {
+        match self {
+            Size::Small => "small",
+            Size::Medium => "medium",
+            Size::Large => "large",
+        }
+    }
+    
+    /// Get a short description for listings and help
+    pub fn description(self) -> &'static str {
+        match self {
+            Size::Small => "5x5 slab with 3x3 pockets",
+            Size::Medium => "8x8 slab with 4x4 pockets",
+            Size::Large => "12x12 slab with 5x5 pockets",
+        }
+    }
+    
+    /// Get the next size, wrapping around from the largest to the smallest
+    pub fn next(self) -> Self {
+        match self {
+            Size::Small => Size::Medium,
+            Size::Medium => Size::Large,
+            Size::Large => Size::Small,
+        }
+    }
+}
+
+impl FromStr for Size {
+    type Err = anyhow::Error;
+    
+    fn from_str(name: &str) -> Result<Self> {
+        let name = name.trim();
+        Size::ALL
+            .into_iter()
+            .find(|size| size.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<_> = Size::ALL.iter().map(|size| size.name()).collect();
+                anyhow!("Unknown size '{}', expected one of: {}", name, names.join(", "))
+            })
+    }
+}
+
+impl ValueEnum for Size {
+    fn value_variants<'a>() -> &'a [Self] {
+        &Size::ALL
+    }
+    
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()).help(self.description()))
+    }
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How finely the waffle mesh is subdivided into pockets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Detail {
+    /// A single pocket
+    Minimal,
+    /// About half as many pockets per side
+    Reduced,
+    /// The normal number of pockets for the size
+    Full,
+}
+
 /// The 3D point in space
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct Point3D {
     pub x: f64,
     pub y: f64,
@@ -21,6 +108,35 @@ impl Point3D {
         Self { x, y, z }
     }
     
+    /// Dot product with another vector
+    pub fn dot(&self, other: Point3D) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+    
+    /// Cross product with another vector
+    pub fn cross(&self, other: Point3D) -> Point3D {
+        Point3D::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+    
+    /// Length of the vector from the origin
+    pub fn length(&self) -> f64 {
+        self.dot(*self).sqrt()
+    }
+    
+    /// Unit vector in the same direction (zero stays zero)
+    pub fn normalized(&self) -> Point3D {
+        let length = self.length();
+        if length > 0.0 {
+            *self * (1.0 / length)
+        } else {
+            *self
+        }
+    }
+    
     /// Rotate the point around the y-axis
     pub fn rotate_y(&mut self, angle: f64) {
         let old_x = self.x;
@@ -49,125 +165,185 @@ impl Point3D {
     }
 }
 
-/// Waffle grid cell
+impl Add for Point3D {
+    type Output = Point3D;
+    
+    fn add(self, other: Point3D) -> Point3D {
+        Point3D::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Point3D {
+    type Output = Point3D;
+    
+    fn sub(self, other: Point3D) -> Point3D {
+        Point3D::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<f64> for Point3D {
+    type Output = Point3D;
+    
+    fn mul(self, factor: f64) -> Point3D {
+        Point3D::new(self.x * factor, self.y * factor, self.z * factor)
+    }
+}
+
+impl Neg for Point3D {
+    type Output = Point3D;
+    
+    fn neg(self) -> Point3D {
+        Point3D::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// A point of the untransformed waffle model
+#[derive(Debug, Clone, Copy)]
+pub struct Vertex {
+    pub position: Point3D,
+    pub is_edge: bool,
+}
+
+/// Waffle grid cell: a model vertex transformed by the current rotation
 #[derive(Debug, Clone)]
 pub struct WaffleCell {
+    /// Index of the model vertex this cell was transformed from
+    pub id: usize,
     pub point: Point3D,
     pub depth: f64,
     pub is_edge: bool,
 }
 
 /// The 3D waffle model
+///
+/// The model vertices never change after construction. Each update transforms
+/// them into `cells` (where `cells[i]` always belongs to vertex `i`) and sorts a
+/// separate draw order, so a cell keeps its identity no matter how often the
+/// waffle is rotated.
 pub struct Waffle {
+    mesh: Mesh,
+    vertices: Vec<Vertex>,
     cells: Vec<WaffleCell>,
+    draw_order: Vec<usize>,
+    normals: Vec<Point3D>,
     size: Size,
+    detail: Detail,
     rotation_x: f64,
     rotation_y: f64,
     rotation_z: f64,
-    original_points: Vec<Point3D>,
+    // Turn made in world space on top of the rotation angles, e.g. by dragging
+    turn: Rotation,
+    // How long the last update of the rotated cells took
+    transform_time: Duration,
 }
 
 impl Waffle {
     pub fn new(size: Size) -> Self {
-        let (width, height, depth, grid_size) = match size {
-            Size::Small => (5.0, 5.0, 1.0, 10),
-            Size::Medium => (8.0, 8.0, 1.5, 16),
-            Size::Large => (12.0, 12.0, 2.0, 24),
+        Self::with_detail(size, Detail::Full)
+    }
+    
+    /// Create a waffle with a coarser or finer mesh
+    pub fn with_detail(size: Size, detail: Detail) -> Self {
+        let mut waffle = Self::from_mesh(size, Mesh::waffle(&Self::detailed_shape(size, detail)));
+        waffle.detail = detail;
+        waffle
+    }
+    
+    /// Get the slab dimensions used for a waffle size
+    pub fn shape(size: Size) -> WaffleShape {
+        Self::detailed_shape(size, Detail::Full)
+    }
+    
+    /// Get the slab dimensions used for a waffle size at a level of detail
+    pub fn detailed_shape(size: Size, detail: Detail) -> WaffleShape {
+        let (width, height, thickness, pockets): (f64, f64, f64, usize) = match size {
+            Size::Small => (5.0, 5.0, 1.0, 3),
+            Size::Medium => (8.0, 8.0, 1.5, 4),
+            Size::Large => (12.0, 12.0, 2.0, 5),
+        };
+        let pockets = match detail {
+            Detail::Full => pockets,
+            Detail::Reduced => pockets.div_ceil(2),
+            Detail::Minimal => 1,
         };
         
-        // Create the basic waffle grid
-        let mut cells = Vec::new();
-        let mut original_points = Vec::new();
-        
-        // Create top and bottom grids
-        for z in [-depth / 2.0, depth / 2.0].iter() {
-            for y in (0..grid_size).map(|i| height * (i as f64 / grid_size as f64 - 0.5)) {
-                for x in (0..grid_size).map(|i| width * (i as f64 / grid_size as f64 - 0.5)) {
-                    let is_edge = x.abs() >= (width / 2.0) - 0.1 || 
-                                  y.abs() >= (height / 2.0) - 0.1;
-                    
-                    let point = Point3D::new(x, y, *z);
-                    original_points.push(point);
-                    
-                    cells.push(WaffleCell {
-                        point,
-                        depth: point.z,
-                        is_edge,
-                    });
-                }
-            }
-        }
-        
-        // Create the vertical connections between top and bottom
-        for x in [-(width / 2.0), width / 2.0].iter() {
-            for y in [-(height / 2.0), height / 2.0].iter() {
-                for z in (0..5).map(|i| depth * (i as f64 / 4.0 - 0.5)) {
-                    let point = Point3D::new(*x, *y, z);
-                    original_points.push(point);
-                    
-                    cells.push(WaffleCell {
-                        point,
-                        depth: point.z,
-                        is_edge: true,
-                    });
-                }
-            }
-        }
-        
-        // Create the waffle grid lines
-        let grid_step = width / 4.0;
-        for i in 1..4 {
-            let pos = i as f64 * grid_step - width / 2.0;
-            
-            // Horizontal lines
-            for y in [-height / 2.0, height / 2.0].iter() {
-                for z in (0..5).map(|i| depth * (i as f64 / 4.0 - 0.5)) {
-                    let point = Point3D::new(pos, *y, z);
-                    original_points.push(point);
-                    
-                    cells.push(WaffleCell {
-                        point,
-                        depth: point.z,
-                        is_edge: true,
-                    });
-                }
-            }
-            
-            // Vertical lines
-            for x in [-width / 2.0, width / 2.0].iter() {
-                for z in (0..5).map(|i| depth * (i as f64 / 4.0 - 0.5)) {
-                    let point = Point3D::new(*x, pos, z);
-                    original_points.push(point);
-                    
-                    cells.push(WaffleCell {
-                        point,
-                        depth: point.z,
-                        is_edge: true,
-                    });
-                }
-            }
+        WaffleShape {
+            width,
+            height,
+            thickness,
+            pockets,
+            ridge: width / (pockets as f64 * 3.0 + 1.0),
+            pocket_depth: thickness * 0.5,
         }
+    }
+    
+    /// Create a waffle from an existing mesh
+    pub fn from_mesh(size: Size, mesh: Mesh) -> Self {
+        // Vertices on the outer rim of the slab are drawn as edges
+        let radius_x = mesh.vertices().iter().map(|p| p.x.abs()).fold(0.0, f64::max);
+        let radius_y = mesh.vertices().iter().map(|p| p.y.abs()).fold(0.0, f64::max);
+        let vertices = mesh
+            .vertices()
+            .iter()
+            .map(|&position| Vertex {
+                position,
+                is_edge: position.x.abs() >= radius_x - 0.1 || position.y.abs() >= radius_y - 0.1,
+            })
+            .collect();
         
-        Self {
-            cells,
+        let mut waffle = Self {
+            mesh,
+            vertices,
+            cells: Vec::new(),
+            draw_order: Vec::new(),
+            normals: Vec::new(),
             size,
+            detail: Detail::Full,
             rotation_x: 0.0,
             rotation_y: 0.0,
             rotation_z: 0.0,
-            original_points,
-        }
+            turn: Rotation::IDENTITY,
+            transform_time: Duration::ZERO,
+        };
+        waffle.update_points();
+        waffle
     }
     
-    /// Get a reference to all waffle cells
+    /// Get the untransformed mesh the waffle is built from
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+    
+    /// Get the face normals rotated into the current orientation
+    pub fn normals(&self) -> &[Point3D] {
+        &self.normals
+    }
+    
+    /// Get the untransformed model vertices
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+    
+    /// Get all waffle cells in model order (`cells()[i]` belongs to `vertices()[i]`)
     pub fn cells(&self) -> &[WaffleCell] {
         &self.cells
     }
     
+    /// Iterate over the cells sorted by depth, closest first
+    pub fn sorted_cells(&self) -> impl Iterator<Item = &WaffleCell> {
+        self.draw_order.iter().map(move |&i| &self.cells[i])
+    }
+    
     /// Get the current size
     pub fn size(&self) -> Size {
         self.size
     }
     
+    /// Get the level of detail the mesh was built with
+    pub fn detail(&self) -> Detail {
+        self.detail
+    }
+    
     /// Set the rotation angles
     pub fn set_rotation(&mut self, x: f64, y: f64, z: f64) {
         self.rotation_x = x;
@@ -181,23 +357,61 @@ impl Waffle {
         (self.rotation_x, self.rotation_y, self.rotation_z)
     }
     
+    /// Turn the waffle around axes in world space, on top of its current orientation
+    pub fn turn(&mut self, rotation: Rotation) {
+        self.turn = self.turn.then(rotation);
+        self.update_points();
+    }
+    
+    /// Get the turn made with [`Waffle::turn`] so far
+    pub fn view_turn(&self) -> Rotation {
+        self.turn
+    }
+    
+    /// Replace the turn made with [`Waffle::turn`], e.g. with [`Rotation::IDENTITY`] to undo it
+    pub fn set_view_turn(&mut self, turn: Rotation) {
+        self.turn = turn;
+        self.update_points();
+    }
+    
+    /// Get the full orientation: the rotation angles followed by the view turn
+    pub fn orientation(&self) -> Rotation {
+        Rotation::from_euler(self.rotation_x, self.rotation_y, self.rotation_z).then(self.turn)
+    }
+    
+    /// Get how long rotating the cells into the current orientation took
+    pub fn transform_time(&self) -> Duration {
+        self.transform_time
+    }
+    
     /// Update all points based on current rotation
     fn update_points(&mut self) {
-        for (i, original) in self.original_points.iter().enumerate() {
-            let mut point = *original;
-            
-            // Apply rotations
-            point.rotate_x(self.rotation_x);
-            point.rotate_y(self.rotation_y);
-            point.rotate_z(self.rotation_z);
+        let start = Instant::now();
+        let orientation = self.orientation();
+        
+        // Always transform from the model, never from the previous frame
+        self.cells.clear();
+        self.cells.extend(self.vertices.iter().enumerate().map(|(id, vertex)| {
+            let point = orientation.apply(vertex.position);
             
-            // Update the cell
-            self.cells[i].point = point;
-            self.cells[i].depth = point.z;
-        }
+            WaffleCell {
+                id,
+                point,
+                depth: point.z,
+                is_edge: vertex.is_edge,
+            }
+        }));
+        
+        self.normals.clear();
+        self.normals.extend(self.mesh.normals().iter().map(|&normal| orientation.apply(normal)));
         
-        // Sort cells by z-value for proper rendering (back-to-front)
-        self.cells.sort_by(|a, b| b.depth.partial_cmp(&a.depth).unwrap_or(std::cmp::Ordering::Equal));
+        // Sort the draw order by z-value, leaving the cells themselves in model order
+        let cells = &self.cells;
+        self.draw_order.clear();
+        self.draw_order.extend(0..cells.len());
+        self.draw_order
+            .sort_by(|&a, &b| cells[b].depth.partial_cmp(&cells[a].depth).unwrap_or(std::cmp::Ordering::Equal));
+        self.transform_time = start.elapsed();
     }
     
     /// Update waffle rotation by increment
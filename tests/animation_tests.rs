@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod tests {
+    use glowing_waffle::{
+        animation::{Animation, AnimationType},
+        clock::{FixedStepClock, ManualClock},
+        waffle::{Size, Waffle},
+    };
+    use std::time::Duration;
+    
+    const TYPES: [AnimationType; 4] = [
+        AnimationType::Rotate,
+        AnimationType::Pulse,
+        AnimationType::Wave,
+        AnimationType::Bounce,
+    ];
+    
+    fn run(animation_type: AnimationType, fps: u32, frames: usize) -> (f64, f64, f64) {
+        let mut animation = Animation::with_clock(FixedStepClock::from_fps(fps));
+        animation.set_animation_type(animation_type);
+        let mut waffle = Waffle::new(Size::Small);
+        
+        for _ in 0..frames {
+            animation.update(&mut waffle);
+        }
+        
+        waffle.rotation()
+    }
+    
+    fn assert_close(a: (f64, f64, f64), b: (f64, f64, f64), tolerance: f64) {
+        assert!(
+            (a.0 - b.0).abs() < tolerance && (a.1 - b.1).abs() < tolerance && (a.2 - b.2).abs() < tolerance,
+            "{:?} and {:?} differ by more than {}",
+            a,
+            b,
+            tolerance
+        );
+    }
+    
+    #[test]
+    fn test_identical_inputs_are_reproducible() {
+        for animation_type in TYPES {
+            assert_eq!(run(animation_type, 30, 90), run(animation_type, 30, 90));
+        }
+    }
+    
+    #[test]
+    fn test_speed_is_independent_of_frame_rate() {
+        // One second of rotation at 30 and 120 FPS should end up in the same place
+        assert_close(run(AnimationType::Rotate, 30, 30), run(AnimationType::Rotate, 120, 120), 1e-6);
+        
+        // Time-varying animations converge as the step shrinks
+        for animation_type in TYPES {
+            assert_close(run(animation_type, 60, 120), run(animation_type, 240, 480), 0.02);
+        }
+    }
+    
+    #[test]
+    fn test_manual_clock_drives_animation() {
+        let clock = ManualClock::new();
+        let mut animation = Animation::with_clock(clock.clone());
+        let mut waffle = Waffle::new(Size::Small);
+        
+        // No time passed, nothing moves
+        animation.update(&mut waffle);
+        assert_eq!(waffle.rotation(), (0.0, 0.0, 0.0));
+        assert_eq!(animation.elapsed_time(), 0.0);
+        
+        clock.advance(Duration::from_millis(500));
+        animation.update(&mut waffle);
+        assert_eq!(animation.elapsed_time(), 0.5);
+        assert_ne!(waffle.rotation(), (0.0, 0.0, 0.0));
+    }
+    
+    #[test]
+    fn test_advance_matches_clock() {
+        let mut stepped = Animation::with_clock(FixedStepClock::new(Duration::from_millis(20)));
+        let mut manual = Animation::with_clock(ManualClock::new());
+        let mut a = Waffle::new(Size::Medium);
+        let mut b = Waffle::new(Size::Medium);
+        
+        for _ in 0..50 {
+            stepped.update(&mut a);
+            manual.advance(&mut b, Duration::from_millis(20));
+        }
+        
+        assert_eq!(a.rotation(), b.rotation());
+        assert_eq!(stepped.elapsed_time(), 1.0);
+        
+        let ctx = stepped.frame_context();
+        assert_eq!(ctx.frame, 50);
+        assert_eq!(ctx.time, 1.0);
+        assert_eq!(ctx.dt, 0.02);
+    }
+    
+    #[test]
+    fn test_next_animation_restarts_time() {
+        let mut animation = Animation::with_clock(FixedStepClock::from_fps(10));
+        let mut waffle = Waffle::new(Size::Small);
+        
+        animation.update(&mut waffle);
+        assert!(animation.elapsed_time() > 0.0);
+        
+        animation.next_animation();
+        assert_eq!(animation.elapsed_time(), 0.0);
+    }    
+    #[test]
+    fn test_reset_starts_over() {
+        let clock = ManualClock::new();
+        let mut animation = Animation::with_clock(clock.clone());
+        animation.set_animation_type(AnimationType::Wave);
+        let mut waffle = Waffle::new(Size::Small);
+        
+        clock.advance(Duration::from_secs(1));
+        animation.update(&mut waffle);
+        clock.advance(Duration::from_millis(500));
+        animation.reset();
+        
+        // Time counted before the reset doesn't reach the next update
+        animation.update(&mut waffle);
+        assert_eq!(animation.elapsed_time(), 0.0);
+        assert_eq!(animation.animation_type(), AnimationType::Wave);
+        assert_eq!(animation.frame_context().time, 0.0);
+    }
+}
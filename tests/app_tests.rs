@@ -0,0 +1,321 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+    use glowing_waffle::{
+        animation::Animation,
+        app::{App, MAX_FPS, MIN_FPS},
+        bloom::Bloom,
+        clock::ManualClock,
+        event_loop::{AppEvent, Control, EventHandler},
+        export::{RecordFormat, Recorder},
+        framebuffer::HeadlessTarget,
+        governor::Quality,
+        input::{Action, KeyMap},
+        renderer::{ColorMode, Renderer},
+        waffle::{Detail, Size, Waffle},
+    };
+    
+    fn app() -> (App, ManualClock) {
+        let clock = ManualClock::new();
+        let mut renderer = Renderer::new(ColorMode::Normal);
+        renderer.set_bloom(Bloom::disabled());
+        let animation = Animation::with_clock(clock.clone());
+        let app = App::new(Waffle::new(Size::Medium), renderer, animation, KeyMap::default(), 30);
+        (app, clock)
+    }
+    
+    fn press(app: &mut App, ch: char) -> Option<Action> {
+        app.handle_key(&KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE))
+    }
+    
+    #[test]
+    fn test_pause_and_step() {
+        let (mut app, clock) = app();
+        
+        press(&mut app, ' ');
+        clock.advance(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.waffle().rotation(), (0.0, 0.0, 0.0), "Paused waffle should not move");
+        
+        // Stepping advances exactly one frame and stays paused
+        assert_eq!(press(&mut app, '.'), Some(Action::Step));
+        let (x, _, _) = app.waffle().rotation();
+        assert!((x - 0.3 / 30.0).abs() < 1e-6);
+        assert!(app.animation().is_paused());
+        
+        // Resuming doesn't make up for the time spent paused
+        press(&mut app, ' ');
+        for _ in 0..30 {
+            clock.advance(Duration::from_secs(1) / 30);
+            app.update();
+        }
+        
+        // Frames are drawn up to one fixed update behind the simulation
+        let (x, _, _) = app.waffle().rotation();
+        let simulated = 0.3 / 30.0 + 0.3;
+        assert!(x <= simulated + 1e-6 && x >= simulated - 0.3 / 60.0 - 1e-6, "Rotation {} is off", x);
+    }
+    
+    #[test]
+    fn test_speed_and_fps_limits() {
+        let (mut app, _) = app();
+        
+        press(&mut app, '+');
+        assert!(app.animation().speed() > 1.0);
+        for _ in 0..100 {
+            press(&mut app, '-');
+        }
+        assert!(app.animation().speed() > 0.0, "Speed should never reach zero");
+        
+        for _ in 0..100 {
+            press(&mut app, ']');
+        }
+        assert_eq!(app.fps(), MAX_FPS);
+        for _ in 0..100 {
+            press(&mut app, '[');
+        }
+        assert_eq!(app.fps(), MIN_FPS);
+        assert_eq!(app.frame_duration(), Duration::from_secs(1));
+        
+        press(&mut app, 'r');
+        assert_eq!(app.animation().speed(), 1.0);
+    }
+    
+    #[test]
+    fn test_cycling() {
+        let (mut app, _) = app();
+        app.handle_action(Action::Step);
+        let rotation = app.waffle().rotation();
+        
+        press(&mut app, 's');
+        assert_eq!(app.waffle().size(), Size::Large);
+        assert_eq!(app.waffle().rotation(), rotation, "Changing size keeps the orientation");
+        
+        press(&mut app, 'c');
+        assert_eq!(app.renderer().color_mode(), ColorMode::Rainbow);
+        
+        let before = app.animation().animation_type();
+        press(&mut app, 'a');
+        assert_ne!(app.animation().animation_type(), before);
+        
+        assert!(!app.should_quit());
+        press(&mut app, 'q');
+        assert!(app.should_quit());
+    }
+    
+    #[test]
+    fn test_help_overlay() {
+        let (mut app, _) = app();
+        let mut target = HeadlessTarget::new(80, 30);
+        
+        app.render(&mut target).unwrap();
+        assert!(!target.frame().to_text().contains("Keys"));
+        
+        press(&mut app, '?');
+        app.render(&mut target).unwrap();
+        let text = target.frame().to_text();
+        assert!(text.contains("Keys"));
+        assert!(text.contains(Action::NextColor.description()));
+        
+        // Hiding the help while paused leaves a hint
+        press(&mut app, '?');
+        press(&mut app, ' ');
+        app.render(&mut target).unwrap();
+        let text = target.frame().to_text();
+        assert!(!text.contains("Keys") && text.contains("Paused"));
+    }
+    
+    fn mouse(app: &mut App, kind: MouseEventKind, column: u16, row: u16) {
+        app.handle_mouse(&MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        });
+    }
+    
+    #[test]
+    fn test_mouse_takes_control_from_animation() {
+        let (mut app, clock) = app();
+        let mut target = HeadlessTarget::new(80, 24);
+        app.render(&mut target).unwrap();
+        
+        mouse(&mut app, MouseEventKind::Down(MouseButton::Left), 40, 12);
+        mouse(&mut app, MouseEventKind::Drag(MouseButton::Left), 40, 18);
+        let orientation = app.waffle().orientation();
+        assert!(app.waffle().view_turn().angle() > 0.0);
+        let (x, _, _) = app.waffle().rotation();
+        
+        // While held still, the animation doesn't move the waffle
+        clock.advance(Duration::from_secs(1));
+        app.update();
+        assert_eq!(app.waffle().orientation(), orientation);
+        
+        // The release key hands control back straight away
+        mouse(&mut app, MouseEventKind::Up(MouseButton::Left), 40, 18);
+        press(&mut app, 'g');
+        assert!(!app.trackball().is_active());
+        clock.advance(Duration::from_secs(1));
+        app.update();
+        assert!(app.waffle().rotation().0 > x);
+    }
+    
+    #[test]
+    fn test_scroll_zooms() {
+        let (mut app, _) = app();
+        let mut target = HeadlessTarget::new(80, 24);
+        app.render(&mut target).unwrap();
+        let fitted = app.renderer().camera().target_distance();
+        
+        mouse(&mut app, MouseEventKind::ScrollUp, 0, 0);
+        let zoomed = app.renderer().camera().target_distance();
+        assert!(zoomed < fitted);
+        
+        // Zoom survives the camera being refitted after a resize
+        let mut resized = HeadlessTarget::new(120, 40);
+        app.render(&mut resized).unwrap();
+        assert!((app.renderer().zoom_level() - 1.1).abs() < 1e-9);
+        
+        for _ in 0..100 {
+            mouse(&mut app, MouseEventKind::ScrollDown, 0, 0);
+        }
+        assert!(app.renderer().zoom_level() >= 0.25 - 1e-9, "Zoom is limited");
+        
+        press(&mut app, 'r');
+        assert_eq!(app.renderer().zoom_level(), 1.0);
+    }
+    
+    #[test]
+    fn test_event_handler() {
+        let (mut app, _) = app();
+        let mut target = HeadlessTarget::new(80, 24);
+        let key = |ch| AppEvent::Key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE));
+        
+        assert_eq!(app.handle_event(AppEvent::Tick, &mut target).unwrap(), Control::Continue);
+        assert_eq!(target.frames_presented(), 1, "Ticks draw a frame");
+        
+        assert_eq!(
+            app.handle_event(key(']'), &mut target).unwrap(),
+            Control::SetFrameDuration(Duration::from_secs_f64(1.0 / 35.0))
+        );
+        assert_eq!(app.handle_event(key('q'), &mut target).unwrap(), Control::Quit);
+    }
+    
+    #[test]
+    fn test_animation_speed_independent_of_frame_rate() {
+        let rotation_after = |frames: u32| {
+            let (mut app, clock) = app();
+            for _ in 0..frames {
+                clock.advance(Duration::from_secs(2) / frames);
+                app.update();
+            }
+            app.waffle().rotation().1
+        };
+        
+        // Two seconds at 10, 30 or 120 fps turn the waffle equally far, within one update
+        let expected = 0.6 * 2.0;
+        for frames in [20, 60, 240] {
+            let y = rotation_after(frames);
+            assert!((y - expected).abs() <= 0.6 / 60.0 + 1e-6, "{} frames turned {}", frames, y);
+        }
+    }
+    
+    #[test]
+    fn test_frames_interpolate_between_updates() {
+        let (mut app, clock) = app();
+        
+        // A quarter and three quarters of the way into the first update
+        let step = app.timestep().step();
+        clock.advance(step + step / 4);
+        app.update();
+        let quarter = app.waffle().rotation().1;
+        clock.advance(step / 2);
+        app.update();
+        let three_quarters = app.waffle().rotation().1;
+        
+        let per_step = 0.6 * step.as_secs_f64();
+        assert!((quarter - per_step * 0.25).abs() < 1e-6);
+        assert!((three_quarters - per_step * 0.75).abs() < 1e-6);
+    }
+    
+    #[test]
+    fn test_frame_context_counts_drawn_frames() {
+        let (mut app, clock) = app();
+        
+        // Each frame runs several fixed updates but counts once, with the frame's own duration
+        let frame = app.timestep().step() * 3;
+        for _ in 0..2 {
+            clock.advance(frame);
+            app.update();
+        }
+        let ctx = app.animation().frame_context();
+        assert_eq!(ctx.frame, 2);
+        assert!((ctx.dt - frame.as_secs_f64()).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_stats_overlay() {
+        let (mut app, clock) = app();
+        let mut target = HeadlessTarget::new(80, 24);
+        
+        for _ in 0..10 {
+            clock.advance(Duration::from_millis(50));
+            app.update();
+        }
+        assert!((app.stats().fps() - 20.0).abs() < 1e-6);
+        
+        press(&mut app, 'f');
+        app.render(&mut target).unwrap();
+        let text = target.frame().to_text();
+        assert!(text.contains("FPS 20.0/30"));
+        assert!(!text.contains("B/frame"), "A headless target writes no output to count");
+        
+        // Targets that encode frames report what the previous frame cost to send
+        let mut recorder = Recorder::new(Vec::new(), RecordFormat::Ansi, 80, 24, 30).unwrap();
+        app.render(&mut recorder).unwrap();
+        let bytes = app.renderer().output_stats().unwrap().bytes;
+        assert!(bytes > 0);
+        app.render(&mut target).unwrap();
+        assert!(target.frame().to_text().contains(&format!("output {} B/frame", bytes)));
+    }
+    
+    #[test]
+    fn test_profile_overlay() {
+        let (mut app, _clock) = app();
+        let mut target = HeadlessTarget::new(100, 24);
+        app.render(&mut target).unwrap();
+        assert_eq!(app.profile().frames(), 1);
+        assert!(!target.frame().to_text().contains("rasterize"));
+        
+        press(&mut app, 't');
+        assert!(app.is_profile_visible());
+        app.render(&mut target).unwrap();
+        let text = target.frame().to_text();
+        assert!(text.contains("ms: transform "), "{}", text);
+        assert!(text.contains(" emit "));
+    }
+    
+    #[test]
+    fn test_adaptive_quality() {
+        let (mut app, _clock) = app();
+        let mut target = HeadlessTarget::new(80, 24);
+        assert!(app.governor().is_none());
+        assert_eq!(app.quality(), Quality::FULL);
+        
+        app.set_adaptive(true);
+        app.set_stats_visible(true);
+        app.render(&mut target).unwrap();
+        assert!(target.frame().to_text().contains("quality 0/"));
+        
+        // Frame rate keys move the governor's budget along with the target
+        press(&mut app, ']');
+        assert_eq!(app.effective_fps(), 35);
+        assert_eq!(app.frame_duration(), Duration::from_secs_f64(1.0 / 35.0));
+        
+        app.set_adaptive(false);
+        assert!(app.governor().is_none());
+        assert_eq!(app.waffle().detail(), Detail::Full);
+    }
+}
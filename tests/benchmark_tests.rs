@@ -0,0 +1,267 @@
+#[cfg(test)]
+mod tests {
+    use std::{io, path::Path, time::Duration};
+    
+    use glowing_waffle::{
+        animation::{Animation, AnimationType, FrameContext},
+        benchmark::{self, BenchmarkOptions, BenchmarkReport, Comparison, Delta, FrameStats, Metric, ReportFormat},
+        clock::FixedStepClock,
+        export::{RecordFormat, Recorder},
+        framebuffer::{FrameBuffer, HeadlessTarget, RenderTarget},
+        renderer::{ColorMode, Renderer, Stage},
+        waffle::{Size, Waffle},
+    };
+    
+    fn small_report() -> benchmark::BenchmarkReport {
+        let mut target = HeadlessTarget::new(40, 20);
+        let mut renderer = Renderer::new(ColorMode::Rainbow);
+        let mut animation = Animation::with_clock(FixedStepClock::from_fps(30));
+        let options = BenchmarkOptions {
+            frames: 5,
+            warmup: 1,
+            sizes: vec![Size::Small, Size::Medium],
+            ..BenchmarkOptions::default()
+        };
+        benchmark::run(&mut target, &mut renderer, &mut animation, &options).unwrap()
+    }
+    
+    #[test]
+    fn test_frame_stats() {
+        let samples: Vec<Duration> = (1..=100).rev().map(Duration::from_millis).collect();
+        let stats = FrameStats::from_samples(&samples);
+        
+        assert_eq!(stats.frames, 100);
+        assert!((stats.mean_ms - 50.5).abs() < 1e-9);
+        assert!((stats.stddev_ms - 28.866).abs() < 1e-3);
+        assert_eq!((stats.min_ms, stats.max_ms), (1.0, 100.0));
+        assert_eq!((stats.p50_ms, stats.p95_ms, stats.p99_ms), (50.0, 95.0, 99.0));
+        assert!((stats.fps - 1000.0 / 50.5).abs() < 1e-9);
+        
+        assert_eq!(FrameStats::from_samples(&[]), FrameStats::default());
+    }
+    
+    #[test]
+    fn test_run_covers_every_size_and_color_mode() {
+        let report = small_report();
+        assert_eq!((report.width, report.height), (40, 20));
+        assert_eq!(report.results.len(), 2 * ColorMode::ALL.len());
+        
+        for size in [Size::Small, Size::Medium] {
+            for mode in ColorMode::ALL {
+                let result = report.result(size, mode).unwrap();
+                assert_eq!(result.stats.frames, 5);
+                assert!(result.stats.min_ms <= result.stats.p50_ms && result.stats.p50_ms <= result.stats.max_ms);
+                
+                // Every stage is measured and together they make up the whole frame
+                assert_eq!(result.stages.len(), Stage::ALL.len());
+                assert!(result.stage(Stage::Rasterize).unwrap().mean_ms > 0.0);
+                let share: f64 = result.stages.iter().map(|stage| stage.share).sum();
+                assert!((share - 1.0).abs() < 1e-9);
+            }
+        }
+        assert!(report.result(Size::Large, ColorMode::Normal).is_none());
+    }
+    
+    #[test]
+    fn test_json_and_csv_reports() {
+        let report = small_report();
+        
+        let mut json = Vec::new();
+        benchmark::write_report(&report, ReportFormat::Json, &mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.contains("\"width\": 40"), "{}", json);
+        assert!(json.contains("\"size\": \"small\""));
+        assert!(json.contains("\"color_mode\": \"fire\""));
+        assert!(json.contains("\"p99_ms\""));
+        assert!(json.contains("\"rasterize\": {"));
+        
+        let mut csv = Vec::new();
+        benchmark::write_report(&report, ReportFormat::Csv, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "size,color_mode,frames,mean_ms,stddev_ms,min_ms,p50_ms,p95_ms,p99_ms,max_ms,fps,\
+             transform_ms,project_ms,rasterize_ms,shade_ms,emit_ms,\
+             bytes_per_frame,max_bytes,escapes_per_frame,cells_changed_per_frame"
+        );
+        assert_eq!(lines.len(), 1 + report.results.len());
+        assert!(lines[1].starts_with("small,normal,5,"));
+        assert!(lines.iter().all(|line| line.split(',').count() == 20));
+        
+        // Nothing was encoded, so there is no output to report
+        assert!(lines[1].ends_with(",,,,"));
+        assert!(!json.contains("bytes_per_frame"));
+        
+        assert_eq!(ReportFormat::from_path(Path::new("out/bench.JSON")), Some(ReportFormat::Json));
+        assert_eq!(ReportFormat::from_path(Path::new("bench.csv")), Some(ReportFormat::Csv));
+        assert_eq!(ReportFormat::from_path(Path::new("bench")), None);
+    }
+    
+    #[test]
+    fn test_baseline_round_trip() {
+        let report = small_report();
+        let path = std::env::temp_dir().join(format!("glowing-waffle-baseline-{}.json", std::process::id()));
+        report.save(&path).unwrap();
+        let loaded = BenchmarkReport::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        
+        assert_eq!(loaded.results.len(), report.results.len());
+        let (before, after) = (&report.results[1], &loaded.results[1]);
+        assert_eq!((after.size, after.color_mode), (before.size, before.color_mode));
+        assert!((after.stats.p95_ms - before.stats.p95_ms).abs() < 1e-9);
+        assert_eq!(after.stages.len(), Stage::ALL.len());
+        
+        // Stages are optional, so a trimmed-down baseline still loads
+        let minimal = r#"{"version": 1, "width": 80, "height": 24, "frames": 1, "warmup": 0, "results": [{"size": "small",
+            "color_mode": "fire", "frames": 1, "mean_ms": 1.0, "stddev_ms": 0.0, "min_ms": 1.0, "p50_ms": 1.0,
+            "p95_ms": 1.0, "p99_ms": 1.0, "max_ms": 1.0, "fps": 1000.0}]}"#;
+        let parsed = BenchmarkReport::parse(minimal).unwrap();
+        assert_eq!(parsed.results[0].color_mode, ColorMode::Fire);
+        assert!(parsed.results[0].stages.is_empty());
+        
+        assert!(BenchmarkReport::parse(&minimal.replace("\"fire\"", "\"smoke\"")).is_err());
+        
+        // Reports from before the layout was versioned, or from another version, aren't comparable
+        assert!(BenchmarkReport::parse(&minimal.replace(r#""version": 1, "#, "")).is_err());
+        assert!(BenchmarkReport::parse(&minimal.replace(r#""version": 1"#, r#""version": 99"#)).is_err());
+    }
+    
+    #[test]
+    fn test_comparison_flags_regressions() {
+        let baseline = small_report();
+        let mut current = baseline.clone();
+        current.results[0].stats.p50_ms *= 1.25;
+        current.results[1].stats.p50_ms *= 1.05;
+        current.results[2].stats.p50_ms *= 0.5;
+        current.results.truncate(4);
+        let mut extra = current.results[3].clone();
+        extra.size = Size::Large;
+        current.results.push(extra);
+        
+        let comparison = Comparison::new(&baseline, &current, Metric::P50, 0.1);
+        assert_eq!(comparison.deltas.len(), 4);
+        assert_eq!(comparison.unmatched, 1);
+        assert!((comparison.deltas[0].change() - 0.25).abs() < 1e-9);
+        assert!((comparison.deltas[2].change() + 0.5).abs() < 1e-9);
+        
+        let regressions: Vec<_> = comparison.regressions().collect();
+        assert_eq!(regressions.len(), 1);
+        assert_eq!((regressions[0].size, regressions[0].color_mode), (Size::Small, ColorMode::Normal));
+        
+        // A looser threshold or another metric lets it pass
+        assert_eq!(Comparison::new(&baseline, &current, Metric::P50, 0.3).regressions().count(), 0);
+        assert_eq!(Comparison::new(&baseline, &current, Metric::Mean, 0.1).regressions().count(), 0);
+        
+        let mut table = Vec::new();
+        benchmark::write_comparison(&comparison, &mut table).unwrap();
+        let table = String::from_utf8(table).unwrap();
+        assert!(table.contains("+25.0%  REGRESSION"), "{}", table);
+        assert!(table.contains("1 results have no baseline"));
+        
+        // Regressions fail the check first, then results missing from the baseline unless allowed
+        assert!(comparison.check(true).unwrap_err().to_string().contains("1 of 4 results"));
+        current.results[0].stats.p50_ms = baseline.results[0].stats.p50_ms;
+        let comparison = Comparison::new(&baseline, &current, Metric::P50, 0.1);
+        assert!(comparison.check(false).unwrap_err().to_string().contains("1 results have no baseline"));
+        comparison.check(true).unwrap();
+        
+        // Nothing in common is never a pass
+        let mut unrelated = baseline.clone();
+        unrelated.results.retain(|result| result.size != Size::Small);
+        current.results.retain(|result| result.size == Size::Small);
+        assert!(Comparison::new(&unrelated, &current, Metric::P50, 0.1).check(true).is_err());
+    }
+    
+    #[test]
+    fn test_zero_baseline() {
+        let delta = |baseline_ms, current_ms| Delta {
+            size: Size::Small,
+            color_mode: ColorMode::Normal,
+            baseline_ms,
+            current_ms,
+        };
+        assert_eq!(delta(0.0, 0.0).change(), 0.0);
+        assert_eq!(delta(0.0, 0.5).change(), f64::INFINITY);
+        
+        // Anything slower than nothing at all is a regression
+        let current = small_report();
+        let mut baseline = current.clone();
+        baseline.results[0].stats.p50_ms = 0.0;
+        let comparison = Comparison::new(&baseline, &current, Metric::P50, 0.1);
+        assert_eq!(comparison.regressions().count(), 1);
+        assert!(comparison.check(true).is_err());
+    }
+    
+    #[test]
+    fn test_runs_do_not_depend_on_earlier_runs() {
+        let run = |sizes: Vec<Size>, color_modes: Vec<ColorMode>| {
+            let mut target = Recorder::new(io::sink(), RecordFormat::Ansi, 40, 20, 30).unwrap();
+            let mut renderer = Renderer::new(ColorMode::Normal);
+            let mut animation = Animation::with_clock(FixedStepClock::from_fps(30));
+            animation.set_animation_type(AnimationType::Wave);
+            let options = BenchmarkOptions {
+                frames: 5,
+                warmup: 1,
+                sizes,
+                color_modes,
+            };
+            benchmark::run(&mut target, &mut renderer, &mut animation, &options).unwrap()
+        };
+        
+        // The same frames are drawn, so the same output is sent, with or without runs before it
+        let sweep = run(vec![Size::Small, Size::Medium], vec![ColorMode::Normal, ColorMode::Rainbow]);
+        let alone = run(vec![Size::Medium], vec![ColorMode::Rainbow]);
+        let expected = sweep.result(Size::Medium, ColorMode::Rainbow).unwrap();
+        assert!(alone.results[0].output.is_some());
+        assert_eq!(alone.results[0].output, expected.output);
+        assert_eq!(alone.results[0].stats.frames, expected.stats.frames);
+    }
+    
+    #[test]
+    fn test_encoding_target_reports_output() {
+        let mut target = Recorder::new(io::sink(), RecordFormat::Ansi, 40, 20, 30).unwrap();
+        let mut renderer = Renderer::new(ColorMode::Rainbow);
+        let mut animation = Animation::with_clock(FixedStepClock::from_fps(30));
+        let options = BenchmarkOptions {
+            frames: 5,
+            warmup: 1,
+            sizes: vec![Size::Small],
+            color_modes: vec![ColorMode::Rainbow],
+        };
+        let report = benchmark::run(&mut target, &mut renderer, &mut animation, &options).unwrap();
+        
+        let output = report.results[0].output.unwrap();
+        assert!(output.bytes_per_frame > 0.0);
+        assert!(output.max_bytes as f64 >= output.bytes_per_frame);
+        assert!(output.escapes_per_frame >= 1.0);
+        assert!(output.cells_changed_per_frame > 0.0);
+        
+        let mut table = Vec::new();
+        benchmark::write_report(&report, ReportFormat::Table, &mut table).unwrap();
+        assert!(String::from_utf8(table).unwrap().contains("Output per frame"));
+    }
+    
+    /// Target whose output has gone away, like a closed terminal
+    struct BrokenTarget;
+    
+    impl RenderTarget for BrokenTarget {
+        fn size(&self) -> (u16, u16) {
+            (40, 20)
+        }
+        
+        fn present(&mut self, _frame: &FrameBuffer) -> anyhow::Result<()> {
+            Err(io::Error::from(io::ErrorKind::BrokenPipe).into())
+        }
+    }
+    
+    #[test]
+    fn test_render_errors_are_not_timed() {
+        let mut renderer = Renderer::new(ColorMode::Normal);
+        let mut waffle = Waffle::new(Size::Small);
+        let ctx = FrameContext::default();
+        
+        benchmark::benchmark_frame(&mut HeadlessTarget::new(40, 20), &mut renderer, &mut waffle, &ctx).unwrap();
+        assert!(benchmark::benchmark_frame(&mut BrokenTarget, &mut renderer, &mut waffle, &ctx).is_err());
+    }
+}
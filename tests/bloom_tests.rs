@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Color;
+    use glowing_waffle::{
+        bloom::Bloom,
+        color,
+        framebuffer::{Cell, FrameBuffer},
+    };
+    
+    const WHITE: Color = Color::Rgb { r: 255, g: 255, b: 255 };
+    const DIM: Color = Color::Rgb { r: 40, g: 40, b: 40 };
+    
+    fn single_cell(color: Color) -> FrameBuffer {
+        let mut frame = FrameBuffer::new(21, 11);
+        frame.set(10, 5, Cell::new('@', color, 1.0));
+        frame
+    }
+    
+    fn glow_at(frame: &FrameBuffer, x: u16, y: u16) -> f64 {
+        frame.get(x, y).filter(|cell| !cell.is_empty()).map_or(0.0, |cell| color::luminance(cell.color))
+    }
+    
+    #[test]
+    fn test_bright_cells_glow_with_falloff() {
+        let mut frame = single_cell(WHITE);
+        Bloom::new(2, 1.0).apply(&mut frame);
+        
+        // The source cell itself is untouched
+        assert_eq!(frame.get(10, 5), Some(&Cell::new('@', WHITE, 1.0)));
+        
+        // Glow fades with distance in both directions
+        assert!(glow_at(&frame, 11, 5) > glow_at(&frame, 13, 5));
+        assert!(glow_at(&frame, 10, 6) > glow_at(&frame, 10, 7));
+        assert!(glow_at(&frame, 10, 6) > 0.0);
+        
+        // Nothing beyond the radius
+        assert_eq!(glow_at(&frame, 10, 8), 0.0);
+        assert_eq!(glow_at(&frame, 16, 5), 0.0);
+    }
+    
+    #[test]
+    fn test_dark_cells_do_not_glow() {
+        let mut frame = single_cell(DIM);
+        let before = frame.clone();
+        Bloom::default().apply(&mut frame);
+        
+        assert_eq!(frame, before);
+    }
+    
+    #[test]
+    fn test_glow_only_fills_empty_cells() {
+        let mut frame = single_cell(WHITE);
+        frame.set(11, 5, Cell::new('x', DIM, 0.0));
+        Bloom::new(3, 1.0).apply(&mut frame);
+        
+        assert_eq!(frame.get(11, 5), Some(&Cell::new('x', DIM, 0.0)));
+        assert!(frame.get(12, 5).is_some_and(|cell| !cell.is_empty()));
+    }
+    
+    #[test]
+    fn test_disabled_and_tuned_bloom() {
+        let mut frame = single_cell(WHITE);
+        let before = frame.clone();
+        
+        Bloom::disabled().apply(&mut frame);
+        assert_eq!(frame, before);
+        
+        Bloom::new(0, 1.0).apply(&mut frame);
+        assert_eq!(frame, before);
+        
+        // A larger radius reaches further
+        let mut small = single_cell(WHITE);
+        let mut large = single_cell(WHITE);
+        Bloom::new(1, 1.0).apply(&mut small);
+        Bloom::new(4, 1.0).apply(&mut large);
+        let count = |frame: &FrameBuffer| frame.cells().iter().filter(|cell| !cell.is_empty()).count();
+        assert!(count(&large) > count(&small));
+    }
+    
+    #[test]
+    fn test_reused_bloom_starts_each_frame_afresh() {
+        // Glow from an earlier frame, even a larger one, never leaks into the next
+        let mut bloom = Bloom::new(2, 1.0);
+        let mut large = FrameBuffer::new(40, 20);
+        large.set(3, 3, Cell::new('@', WHITE, 1.0));
+        bloom.apply(&mut large);
+        
+        let mut reused = single_cell(WHITE);
+        bloom.apply(&mut reused);
+        let mut fresh = single_cell(WHITE);
+        Bloom::new(2, 1.0).apply(&mut fresh);
+        assert_eq!(reused, fresh);
+        assert_eq!(bloom, Bloom::new(2, 1.0));
+    }
+}
@@ -0,0 +1,215 @@
+#[cfg(test)]
+mod tests {
+    use glowing_waffle::{
+        camera::{Camera, Projection},
+        waffle::Point3D,
+    };
+    
+    const EPSILON: f64 = 1e-9;
+    
+    fn project(camera: &Camera, x: f64, y: f64, z: f64) -> Option<(f64, f64, f64)> {
+        camera.project(Point3D::new(x, y, z), 80.0, 24.0).map(|p| (p.x, p.y, p.depth))
+    }
+    
+    #[test]
+    fn test_target_is_centered() {
+        let mut camera = Camera::new(Point3D::new(3.0, -4.0, 10.0), Point3D::new(1.0, 1.0, 1.0));
+        let (x, y, depth) = project(&camera, 1.0, 1.0, 1.0).unwrap();
+        assert!((x - 40.0).abs() < EPSILON && (y - 12.0).abs() < EPSILON && depth.abs() < EPSILON);
+        
+        camera.projection = Projection::Orthographic;
+        let (x, y, _) = project(&camera, 1.0, 1.0, 1.0).unwrap();
+        assert!((x - 40.0).abs() < EPSILON && (y - 12.0).abs() < EPSILON);
+    }
+    
+    #[test]
+    fn test_screen_orientation() {
+        let camera = Camera::default();
+        let (cx, cy, _) = project(&camera, 0.0, 0.0, 0.0).unwrap();
+        let (rx, ry, _) = project(&camera, 1.0, 0.0, 0.0).unwrap();
+        let (ux, uy, _) = project(&camera, 0.0, 1.0, 0.0).unwrap();
+        
+        // +x is to the right and +y is up (towards row 0)
+        assert!(rx > cx && (ry - cy).abs() < EPSILON);
+        assert!(uy < cy && (ux - cx).abs() < EPSILON);
+        
+        // Cells are twice as tall as wide, so one unit spans twice as many columns as rows
+        assert!(((rx - cx) - 2.0 * (cy - uy)).abs() < EPSILON);
+    }
+    
+    #[test]
+    fn test_perspective_shrinks_with_distance() {
+        let camera = Camera::default();
+        let (near_x, _, near_depth) = project(&camera, 1.0, 0.0, 2.0).unwrap();
+        let (far_x, _, far_depth) = project(&camera, 1.0, 0.0, -2.0).unwrap();
+        
+        assert!(near_x > far_x, "Closer points should be further from the center");
+        assert!(near_depth > far_depth, "Closer points should have a larger depth");
+        assert!((near_depth - 2.0).abs() < EPSILON);
+        
+        let mut ortho = camera.clone();
+        ortho.projection = Projection::Orthographic;
+        let (near_x, _, _) = project(&ortho, 1.0, 0.0, 2.0).unwrap();
+        let (far_x, _, _) = project(&ortho, 1.0, 0.0, -2.0).unwrap();
+        assert!((near_x - far_x).abs() < EPSILON, "Orthographic size does not depend on distance");
+    }
+    
+    #[test]
+    fn test_wider_fov_shows_more() {
+        let mut camera = Camera::default();
+        let (narrow, _, _) = project(&camera, 2.0, 0.0, 0.0).unwrap();
+        camera.fov = 90f64.to_radians();
+        let (wide, _, _) = project(&camera, 2.0, 0.0, 0.0).unwrap();
+        
+        assert!(wide < narrow);
+    }
+    
+    #[test]
+    fn test_clipping_instead_of_flipping() {
+        let camera = Camera {
+            near: 1.0,
+            far: 30.0,
+            ..Camera::default()
+        };
+        
+        // Approaching the camera grows without bound instead of dividing by zero or flipping
+        let mut last = 0.0;
+        for z in [0.0, 5.0, 10.0, 14.0, 14.9] {
+            let (x, _, _) = project(&camera, 1.0, 0.0, z).expect("Point in front of the camera");
+            assert!(x.is_finite() && x > last);
+            last = x;
+        }
+        
+        assert!(project(&camera, 1.0, 0.0, 15.5).is_none(), "Inside the near plane");
+        assert!(project(&camera, 1.0, 0.0, 16.0).is_none(), "At the camera");
+        assert!(project(&camera, 1.0, 0.0, 20.0).is_none(), "Behind the camera");
+        assert!(project(&camera, 1.0, 0.0, -20.0).is_none(), "Beyond the far plane");
+    }
+    
+    #[test]
+    fn test_clip_line() {
+        let camera = Camera {
+            near: 1.0,
+            far: 30.0,
+            ..Camera::default()
+        };
+        let in_front = Point3D::new(0.0, 0.0, 5.0);
+        let behind = Point3D::new(0.0, 0.0, 25.0);
+        let beyond = Point3D::new(0.0, 0.0, -25.0);
+        
+        let (start, end) = camera.clip_line(in_front, behind).unwrap();
+        assert!(start == 0.0 && (end - 0.5).abs() < 1e-6, "Cut at the near plane: {:?}", (start, end));
+        let (start, end) = camera.clip_line(beyond, in_front).unwrap();
+        assert!((start - 11.0 / 30.0).abs() < 1e-6 && end == 1.0, "Cut at the far plane: {:?}", (start, end));
+        assert_eq!(camera.clip_line(in_front, Point3D::new(1.0, 2.0, 0.0)), Some((0.0, 1.0)));
+        assert_eq!(camera.clip_line(behind, Point3D::new(0.0, 5.0, 20.0)), None);
+        
+        // The cut ends still project
+        let (start, end) = camera.clip_line(beyond, behind).unwrap();
+        for t in [start, end] {
+            assert!(camera.project(beyond + (behind - beyond) * t, 80.0, 24.0).is_some());
+        }
+    }
+    
+    #[test]
+    fn test_clip_polygon() {
+        let camera = Camera {
+            near: 1.0,
+            ..Camera::default()
+        };
+        let mut clipped = Vec::new();
+        
+        // One corner behind the near plane turns the triangle into a quad
+        let triangle = [Point3D::new(-1.0, 0.0, 0.0), Point3D::new(1.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 20.0)];
+        camera.clip_polygon(&triangle, &mut clipped);
+        assert_eq!(clipped.len(), 4);
+        assert!(clipped.iter().all(|&point| camera.project(point, 80.0, 24.0).is_some()));
+        assert!(clipped.iter().all(|point| point.z <= 15.0));
+        
+        camera.clip_polygon(&triangle[..2], &mut clipped);
+        assert_eq!(clipped, triangle[..2]);
+        
+        let behind = triangle.map(|point| Point3D::new(point.x, point.y, point.z + 20.0));
+        camera.clip_polygon(&behind, &mut clipped);
+        assert!(clipped.is_empty());
+    }
+    
+    #[test]
+    fn test_facing() {
+        let camera = Camera::default();
+        let toward = Point3D::new(0.0, 0.0, 1.0);
+        
+        assert!(camera.is_facing(Point3D::new(0.0, 0.0, 0.0), toward));
+        assert!(!camera.is_facing(Point3D::new(0.0, 0.0, 0.0), -toward));
+        
+        // A sideways face is visible in perspective when it turns towards the camera
+        let side = Point3D::new(-1.0, 0.0, 0.0);
+        assert!(camera.is_facing(Point3D::new(3.0, 0.0, 0.0), side));
+        assert!(!camera.is_facing(Point3D::new(-3.0, 0.0, 0.0), side));
+    }
+    
+    /// Largest distance of a projected sphere point from the screen center, in rows and columns
+    fn sphere_extent(camera: &Camera, radius: f64, width: f64, height: f64) -> (f64, f64) {
+        let (mut columns, mut rows) = (0.0f64, 0.0f64);
+        for i in 0..64 {
+            for j in 0..32 {
+                let (theta, phi) = (i as f64 / 64.0 * std::f64::consts::TAU, j as f64 / 31.0 * std::f64::consts::PI);
+                let point = Point3D::new(phi.sin() * theta.cos(), phi.sin() * theta.sin(), phi.cos()) * radius;
+                let p = camera.project(point, width, height).expect("Sphere should be in front of the camera");
+                columns = columns.max((p.x - width / 2.0).abs());
+                rows = rows.max((p.y - height / 2.0).abs());
+            }
+        }
+        (columns, rows)
+    }
+    
+    #[test]
+    fn test_fit_sphere() {
+        for projection in [Projection::Perspective, Projection::Orthographic] {
+            for (width, height) in [(80.0, 24.0), (30.0, 40.0), (200.0, 60.0)] {
+                let mut camera = Camera {
+                    projection,
+                    ..Camera::default()
+                };
+                camera.fit_sphere(5.0, 0.1, width, height);
+                
+                let (columns, rows) = sphere_extent(&camera, 5.0, width, height);
+                assert!(columns <= width / 2.0 * 0.9 + 1e-6 && rows <= height / 2.0 * 0.9 + 1e-6);
+                
+                // The sphere fills the tighter axis up to the margin
+                let fill = (columns / (width / 2.0)).max(rows / (height / 2.0));
+                assert!(fill > 0.85, "{:?} at {}x{} only fills {}", projection, width, height, fill);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_fit_sphere_keeps_direction() {
+        let mut camera = Camera::new(Point3D::new(4.0, 3.0, 12.0), Point3D::new(0.0, 0.0, 0.0));
+        let forward = camera.forward();
+        camera.fit_sphere(2.0, 0.1, 80.0, 24.0);
+        
+        assert!((camera.forward() - forward).length() < EPSILON);
+        assert_eq!(camera.target, Point3D::new(0.0, 0.0, 0.0));
+    }
+    
+    #[test]
+    fn test_zoom() {
+        let mut camera = Camera::default();
+        camera.zoom(2.0);
+        assert!((camera.target_distance() - 8.0).abs() < EPSILON);
+        
+        // Zooming never moves the camera past its near plane
+        camera.zoom(1000.0);
+        assert!(camera.target_distance() >= camera.near * 2.0);
+        
+        let mut ortho = Camera {
+            projection: Projection::Orthographic,
+            ..Camera::default()
+        };
+        let (x, _, _) = project(&ortho, 1.0, 0.0, 0.0).unwrap();
+        ortho.zoom(2.0);
+        let (zoomed, _, _) = project(&ortho, 1.0, 0.0, 0.0).unwrap();
+        assert!(((zoomed - 40.0) - 2.0 * (x - 40.0)).abs() < EPSILON);
+    }
+}
@@ -0,0 +1,167 @@
+#[cfg(test)]
+mod tests {
+    use glowing_waffle::{
+        animation::AnimationType,
+        config::{Config, Settings},
+        input::{Action, KeyBinding},
+        renderer::ColorMode,
+        waffle::Size,
+    };
+    use clap::ValueEnum;
+    
+    #[test]
+    fn test_parse_config_file() {
+        let config = Config::parse(
+            r#"
+            size = "large"
+            color = "fire"
+            palette = " .oO@"
+            animation = "wave"
+            fps = 45
+            lights = ["dir:1,1,-1:0.6", "point:-6,-4,6"]
+            ambient = 0.2
+            bloom = false
+            cell-aspect = 2.2
+            bindings = ["x=pause", "q=none"]
+            "#,
+        )
+        .unwrap();
+        
+        assert_eq!(config.size, Some(Size::Large));
+        assert_eq!(config.color, Some(ColorMode::Fire));
+        assert_eq!(config.palette, Some(vec![' ', '.', 'o', 'O', '@']));
+        assert_eq!(config.animation, Some(AnimationType::Wave));
+        assert_eq!(config.fps, Some(45));
+        assert_eq!(config.lights.as_ref().map(Vec::len), Some(2));
+        assert_eq!(config.bloom, Some(false));
+        assert_eq!(config.bindings.as_ref().map(Vec::len), Some(2));
+        assert_eq!(config.update_rate, None, "Keys that aren't set stay unset");
+    }
+    
+    #[test]
+    fn test_errors_point_at_the_key() {
+        let err = Config::parse("fps = 30\ncolour = \"fire\"\n").unwrap_err().to_string();
+        assert!(err.contains("line 2") && err.contains("colour"), "{}", err);
+        
+        let err = Config::parse("size = \"huge\"").unwrap_err().to_string();
+        assert!(err.contains("huge") && err.contains("small, medium, large"), "{}", err);
+        
+        let err = Config::parse("cell-aspect = -1.0").unwrap_err().to_string();
+        assert!(err.contains("positive"), "{}", err);
+        
+        let err = Config::parse("bindings = [\"x=dance\"]").unwrap_err().to_string();
+        assert!(err.contains("dance"), "{}", err);
+    }
+    
+    #[test]
+    fn test_environment_variables() {
+        let config = Config::from_vars([
+            ("GLOWING_WAFFLE_BLOOM_RADIUS", "3"),
+            ("GLOWING_WAFFLE_AUTO_FIT", "false"),
+            ("GLOWING_WAFFLE_BINDINGS", "x=pause;y=step"),
+            ("GLOWING_WAFFLE_CONFIG", "/elsewhere.toml"),
+            ("HOME", "/home/waffle"),
+        ])
+        .unwrap();
+        assert_eq!(config.bloom_radius, Some(3));
+        assert_eq!(config.auto_fit, Some(false));
+        assert_eq!(config.bindings.as_ref().map(Vec::len), Some(2));
+        
+        // Unknown variables are skipped rather than stopping the visualizer from starting
+        let config = Config::from_vars([("GLOWING_WAFFLE_FPZ", "30"), ("GLOWING_WAFFLE_FPS", "40")]).unwrap();
+        assert_eq!(config, Config { fps: Some(40), ..Config::default() });
+        let err = Config::from_vars([("GLOWING_WAFFLE_FPS", "fast")]).unwrap_err();
+        assert!(format!("{:#}", err).contains("GLOWING_WAFFLE_FPS"));
+    }
+    
+    #[test]
+    fn test_layer_precedence() {
+        let file = Config::parse("fps = 20\nsize = \"small\"\ncolor = \"rainbow\"\nbindings = [\"x=pause\"]").unwrap();
+        let env = Config::from_vars([("GLOWING_WAFFLE_FPS", "25"), ("GLOWING_WAFFLE_SIZE", "large")]).unwrap();
+        let cli = Config {
+            fps: Some(60),
+            bindings: Some(vec!["x=step".parse().unwrap()]),
+            ..Config::default()
+        };
+        
+        let settings = Settings::from_layers([&file, &env, &cli]);
+        assert_eq!(settings.fps, 60);
+        assert_eq!(settings.size, Size::Large);
+        assert_eq!(settings.color, ColorMode::Rainbow);
+        assert_eq!(settings.update_rate, Settings::default().update_rate);
+        
+        // Later bindings win over earlier ones for the same key
+        let keymap = settings.keymap();
+        assert_eq!(keymap.action(KeyBinding::char('x')), Some(Action::Step));
+        assert_eq!(keymap.action(KeyBinding::char('q')), Some(Action::Quit));
+    }
+    
+    #[test]
+    fn test_settings_build_renderer() {
+        let layer = Config::parse("palette = \" #\"\nbloom = false\northographic = true").unwrap();
+        let renderer = Settings::from_layers([&layer]).renderer();
+        assert_eq!(renderer.char_palette(), &[' ', '#']);
+        assert!(!renderer.bloom().enabled);
+        assert_eq!(renderer.camera().projection, glowing_waffle::camera::Projection::Orthographic);
+    }    
+    #[test]
+    fn test_frame_rate_and_speed_ranges() {
+        assert_eq!(Config::parse("fps = 120\nspeed = 0.5").unwrap().speed, Some(0.5));
+        for text in ["fps = 0", "fps = 500", "speed = 0", "speed = 100.0"] {
+            assert!(Config::parse(text).is_err(), "{} was accepted", text);
+        }
+        assert!(Config::from_vars([("GLOWING_WAFFLE_FPS", "0")]).is_err());
+        
+        let layer = Config::parse("animation = \"bounce\"\nspeed = 2.5").unwrap();
+        let animation = Settings::from_layers([&layer]).animation();
+        assert_eq!(animation.animation_type(), AnimationType::Bounce);
+        assert_eq!(animation.speed(), 2.5);
+    }
+    
+    #[test]
+    fn test_every_key_can_be_set() {
+        for key in Config::KEYS {
+            let err = Config::default().set(key, "\u{0}").map_or_else(|err| err.to_string(), |_| String::new());
+            assert!(!err.contains("Unknown setting"), "{}", err);
+        }
+        assert!(Config::default().set("colour", "fire").is_err());
+    }
+    
+    #[test]
+    fn test_number_ranges_are_rejected_not_clamped() {
+        let cases = [
+            ("ambient", ["-0.1", "1.5", "NaN"]),
+            ("fov", ["0", "180", "NaN"]),
+            ("bloom-intensity", ["-1", "inf", "NaN"]),
+            ("bloom-radius", ["-1", "1000", "1.5"]),
+        ];
+        for (key, values) in cases {
+            for value in values {
+                assert!(Config::default().set(key, value).is_err(), "{} = {} was accepted", key, value);
+                assert!(Config::parse(&format!("{} = {}", key, value)).is_err(), "{} = {} was accepted", key, value);
+            }
+        }
+        
+        let layer = Config::parse("ambient = 0.0\nfov = 120.0\nbloom-intensity = 0.0\nbloom-radius = 4").unwrap();
+        let renderer = Settings::from_layers([&layer]).renderer();
+        assert_eq!(renderer.lighting().ambient, 0.0);
+        assert!((renderer.camera().fov - 120f64.to_radians()).abs() < 1e-9);
+        assert_eq!(renderer.bloom().radius, 4);
+    }
+    
+    #[test]
+    fn test_mode_names_round_trip() {
+        for mode in ColorMode::ALL {
+            assert_eq!(mode.to_string().parse::<ColorMode>().unwrap(), mode);
+            assert_eq!(ColorMode::from_str(mode.name(), false), Ok(mode));
+        }
+        for size in Size::ALL {
+            assert_eq!(size.to_string().parse::<Size>().unwrap(), size);
+            assert_eq!(Size::from_str(size.name(), false), Ok(size));
+        }
+        for animation in AnimationType::ALL {
+            assert_eq!(animation.to_string().parse::<AnimationType>().unwrap(), animation);
+            assert_eq!(AnimationType::from_str(animation.name(), false), Ok(animation));
+        }
+    }
+}
@@ -0,0 +1,175 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        time::{Duration, Instant},
+    };
+    
+    use anyhow::Result;
+    use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+    use glowing_waffle::{
+        event_loop::{self, AppEvent, Control, EventSource},
+        framebuffer::{HeadlessTarget, RenderTarget},
+    };
+    
+    /// Hands out scripted events, then waits out the timeout like a quiet terminal
+    struct ScriptedEvents {
+        events: VecDeque<Event>,
+        timeouts: Vec<Duration>,
+    }
+    
+    impl ScriptedEvents {
+        fn new(events: impl IntoIterator<Item = Event>) -> Self {
+            Self {
+                events: events.into_iter().collect(),
+                timeouts: Vec::new(),
+            }
+        }
+    }
+    
+    impl EventSource for ScriptedEvents {
+        fn next_event(&mut self, timeout: Duration) -> Result<Option<Event>> {
+            self.timeouts.push(timeout);
+            match self.events.pop_front() {
+                Some(event) => Ok(Some(event)),
+                None => {
+                    std::thread::sleep(timeout);
+                    Ok(None)
+                }
+            }
+        }
+    }
+    
+    fn key(ch: char) -> Event {
+        Event::Key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE))
+    }
+    
+    #[test]
+    fn test_dispatches_events_in_order() {
+        let mut target = HeadlessTarget::new(10, 10);
+        let mut source = ScriptedEvents::new([key('a'), Event::Resize(30, 20), Event::FocusLost, key('q')]);
+        let mut seen = Vec::new();
+        
+        let mut handler = |event: AppEvent, target: &mut HeadlessTarget| -> Result<Control> {
+            if let AppEvent::Resize(..) = event {
+                assert_eq!(target.size(), (30, 20), "Target should be resized before the handler runs");
+            }
+            let quit = event == AppEvent::Key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE));
+            seen.push(event);
+            Ok(if quit { Control::Quit } else { Control::Continue })
+        };
+        event_loop::run(&mut target, &mut source, &mut handler, Duration::from_secs(60)).unwrap();
+        
+        assert_eq!(
+            seen,
+            vec![
+                AppEvent::Tick,
+                AppEvent::Key(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE)),
+                AppEvent::Resize(30, 20),
+                AppEvent::FocusLost,
+                AppEvent::Key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE)),
+            ]
+        );
+    }
+    
+    #[test]
+    fn test_waits_for_next_tick_instead_of_polling() {
+        let mut target = HeadlessTarget::new(10, 10);
+        let mut source = ScriptedEvents::new([]);
+        let mut ticks = 0;
+        
+        let frame = Duration::from_millis(20);
+        let start = Instant::now();
+        let mut handler = |event: AppEvent, _: &mut HeadlessTarget| -> Result<Control> {
+            if event == AppEvent::Tick {
+                ticks += 1;
+            }
+            Ok(if ticks == 5 { Control::Quit } else { Control::Continue })
+        };
+        event_loop::run(&mut target, &mut source, &mut handler, frame).unwrap();
+        
+        assert!(start.elapsed() >= frame * 4);
+        
+        // One wait per frame, each for the time left until the next tick
+        assert!(source.timeouts.len() <= 8, "Polled {} times for 4 frames", source.timeouts.len());
+        assert!(source.timeouts.iter().all(|&timeout| timeout <= frame));
+        assert!(source.timeouts.iter().any(|&timeout| timeout > frame / 2));
+    }
+    
+    /// Hands out one event once it's ready, like a key pressed in the middle of a frame
+    struct DelayedEvent {
+        event: Option<Event>,
+        ready: Instant,
+    }
+    
+    impl EventSource for DelayedEvent {
+        fn next_event(&mut self, timeout: Duration) -> Result<Option<Event>> {
+            if Instant::now() < self.ready {
+                std::thread::sleep(timeout.min(self.ready - Instant::now()));
+                return Ok(None);
+            }
+            Ok(self.event.take())
+        }
+    }
+    
+    #[test]
+    fn test_slow_frames_still_read_input() {
+        let mut target = HeadlessTarget::new(10, 10);
+        let mut source = DelayedEvent {
+            event: Some(key('q')),
+            ready: Instant::now() + Duration::from_millis(5),
+        };
+        let mut ticks = 0;
+        
+        // Every frame takes longer than the frame duration, so a tick is always overdue
+        let mut handler = |event: AppEvent, _: &mut HeadlessTarget| -> Result<Control> {
+            match event {
+                AppEvent::Tick => {
+                    ticks += 1;
+                    std::thread::sleep(Duration::from_millis(12));
+                    Ok(if ticks == 20 { Control::Quit } else { Control::Continue })
+                },
+                AppEvent::Key(key) if key.code == KeyCode::Char('q') => Ok(Control::Quit),
+                _ => Ok(Control::Continue),
+            }
+        };
+        event_loop::run(&mut target, &mut source, &mut handler, Duration::from_millis(10)).unwrap();
+        
+        assert_eq!(ticks, 1, "The quit key should be handled right after the first slow frame");
+    }
+    
+    #[test]
+    fn test_frame_duration_can_change() {
+        let mut target = HeadlessTarget::new(10, 10);
+        let mut source = ScriptedEvents::new([]);
+        let mut ticks = Vec::new();
+        
+        let mut handler = |event: AppEvent, _: &mut HeadlessTarget| -> Result<Control> {
+            if event != AppEvent::Tick {
+                return Ok(Control::Continue);
+            }
+            ticks.push(Instant::now());
+            Ok(match ticks.len() {
+                1 => Control::SetFrameDuration(Duration::from_millis(5)),
+                4 => Control::Quit,
+                _ => Control::Continue,
+            })
+        };
+        let start = Instant::now();
+        event_loop::run(&mut target, &mut source, &mut handler, Duration::from_secs(60)).unwrap();
+        
+        assert!(start.elapsed() < Duration::from_secs(5), "The new frame duration should apply straight away");
+        assert!(ticks.windows(2).all(|pair| pair[1] - pair[0] >= Duration::from_millis(5)));
+    }
+    
+    #[test]
+    fn test_errors_end_the_loop() {
+        let mut target = HeadlessTarget::new(10, 10);
+        let mut source = ScriptedEvents::new([]);
+        let mut handler =
+            |_: AppEvent, _: &mut HeadlessTarget| -> Result<Control> { Err(anyhow::anyhow!("Handler failed")) };
+        
+        let result = event_loop::run(&mut target, &mut source, &mut handler, Duration::from_millis(1));
+        assert_eq!(result.unwrap_err().to_string(), "Handler failed");
+    }
+}
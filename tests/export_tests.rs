@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    
+    use crossterm::style::Color;
+    use glowing_waffle::{
+        animation::FrameContext,
+        bloom::Bloom,
+        export::{self, ExportFormat, RecordFormat, Recorder},
+        framebuffer::{Cell, FrameBuffer},
+        renderer::{ColorMode, Renderer},
+        waffle::{Size, Waffle},
+    };
+    
+    fn sample_frame() -> FrameBuffer {
+        let mut frame = FrameBuffer::new(6, 2);
+        frame.set(1, 0, Cell::new('<', Color::Rgb { r: 255, g: 0, b: 0 }, 0.0));
+        frame.set(2, 0, Cell::new('&', Color::Rgb { r: 255, g: 0, b: 0 }, 0.0));
+        frame.set(4, 1, Cell::new('#', Color::Rgb { r: 0, g: 128, b: 255 }, 0.0));
+        frame
+    }
+    
+    fn export(frame: &FrameBuffer, format: ExportFormat) -> String {
+        let mut out = Vec::new();
+        export::write_frame(frame, format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+    
+    #[test]
+    fn test_text_and_ansi_export() {
+        let frame = sample_frame();
+        assert_eq!(export(&frame, ExportFormat::Text), " <&   \n    # \n");
+        
+        let ansi = export(&frame, ExportFormat::Ansi);
+        assert!(ansi.contains("\x1b[38;2;255;0;0m<&"), "{:?}", ansi);
+        assert!(ansi.contains("\x1b[38;2;0;128;255m#"));
+        assert_eq!(ansi.lines().count(), 2);
+        
+        // Without the escape sequences it's the same text
+        let plain: String = ansi.split('\x1b').map(|part| part.split_once('m').map_or(part, |(_, rest)| rest)).collect();
+        assert_eq!(plain, export(&frame, ExportFormat::Text));
+    }
+    
+    #[test]
+    fn test_html_and_svg_export() {
+        let frame = sample_frame();
+        
+        let html = export(&frame, ExportFormat::Html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<span style=\"color: #ff0000\">&lt;&amp;</span>"), "{}", html);
+        assert!(html.contains("#0080ff"));
+        
+        let svg = export(&frame, ExportFormat::Svg);
+        assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains("width=\"54\" height=\"36\""), "{}", svg);
+        assert!(svg.contains(">&lt;&amp;</text>"));
+        assert_eq!(svg.matches("<text").count(), 2, "One element per colored run");
+    }
+    
+    #[test]
+    fn test_formats_from_extension() {
+        assert_eq!(ExportFormat::from_path(Path::new("waffle.SVG")), Some(ExportFormat::Svg));
+        assert_eq!(ExportFormat::from_path(Path::new("out/waffle.htm")), Some(ExportFormat::Html));
+        assert_eq!(ExportFormat::from_path(Path::new("waffle")), None);
+        assert_eq!(RecordFormat::from_path(Path::new("demo.cast")), Some(RecordFormat::Cast));
+        assert_eq!(RecordFormat::from_path(Path::new("demo.png")), None);
+    }
+    
+    #[test]
+    fn test_recorder_writes_asciicast() {
+        let mut renderer = Renderer::new(ColorMode::Rainbow);
+        renderer.set_bloom(Bloom::disabled());
+        let mut waffle = Waffle::new(Size::Small);
+        let mut recorder = Recorder::new(Vec::new(), RecordFormat::Cast, 40, 12, 10).unwrap();
+        
+        for frame in 0..3 {
+            waffle.set_rotation(0.3 * frame as f64, 0.2, 0.0);
+            renderer.render(&mut recorder, &waffle, &FrameContext::default()).unwrap();
+        }
+        assert_eq!(recorder.frames(), 3);
+        
+        let cast = String::from_utf8(recorder.finish().unwrap()).unwrap();
+        let lines: Vec<_> = cast.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains("\"version\": 2") && lines[0].contains("\"width\": 40"));
+        
+        // Frames are a tenth of a second apart and hold escaped terminal output
+        assert!(lines[1].starts_with("[0.000000, \"o\", \"\\u001b[2J"), "{}", lines[1]);
+        assert!(lines[3].starts_with("[0.200000, \"o\", \""));
+        assert!(!lines[2].contains("[2J"), "Later frames only contain what changed");
+    }
+}
@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Color;
+    use glowing_waffle::{
+        animation::FrameContext,
+        framebuffer::{Cell, FrameBuffer, HeadlessTarget, RenderTarget},
+        renderer::{ColorMode, Renderer},
+        waffle::{Size, Waffle},
+    };
+    
+    #[test]
+    fn test_plot_respects_depth() {
+        let mut frame = FrameBuffer::new(4, 2);
+        
+        assert!(frame.plot(1, 1, Cell::new('a', Color::White, 0.5)));
+        assert!(!frame.plot(1, 1, Cell::new('b', Color::White, 0.1)), "Farther cell should be rejected");
+        assert!(frame.plot(1, 1, Cell::new('c', Color::White, 0.9)), "Closer cell should win");
+        assert_eq!(frame.get(1, 1).map(|cell| cell.ch), Some('c'));
+        
+        // Out of bounds writes are ignored
+        assert!(!frame.plot(4, 0, Cell::new('x', Color::White, 1.0)));
+        assert!(frame.get(4, 0).is_none());
+    }
+    
+    #[test]
+    fn test_to_text_dimensions() {
+        let mut frame = FrameBuffer::new(3, 2);
+        frame.set(0, 0, Cell::new('#', Color::White, 0.0));
+        
+        assert_eq!(frame.to_text(), "#  \n   \n");
+        
+        frame.clear();
+        assert!(frame.cells().iter().all(Cell::is_empty));
+    }
+    
+    #[test]
+    fn test_headless_render() {
+        let mut target = HeadlessTarget::new(80, 24);
+        let mut renderer = Renderer::new(ColorMode::Normal);
+        let mut waffle = Waffle::new(Size::Medium);
+        waffle.set_rotation(0.5, 0.2, 0.1);
+        
+        renderer.render(&mut target, &waffle, &FrameContext::default()).unwrap();
+        
+        assert_eq!(target.size(), (80, 24));
+        assert_eq!(target.frames_presented(), 1);
+        assert_eq!(target.frame(), renderer.frame());
+        assert!(
+            target.frame().cells().iter().any(|cell| !cell.is_empty()),
+            "Rendering should draw something into the framebuffer"
+        );
+    }
+    
+    #[test]
+    fn test_headless_render_is_repeatable() {
+        let mut target = HeadlessTarget::new(60, 20);
+        let mut renderer = Renderer::new(ColorMode::Fire);
+        let mut waffle = Waffle::new(Size::Small);
+        waffle.set_rotation(0.3, 0.4, 0.0);
+        
+        renderer.render(&mut target, &waffle, &FrameContext::default()).unwrap();
+        let first = target.frame().clone();
+        
+        renderer.render(&mut target, &waffle, &FrameContext::default()).unwrap();
+        assert_eq!(&first, target.frame(), "Same input should produce the same frame");
+    }
+}
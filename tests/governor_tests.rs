@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    
+    use glowing_waffle::{
+        color::ColorDepth,
+        governor::{Governor, Quality},
+        waffle::Detail,
+    };
+    
+    /// Feed frames of a fixed cost until the quality changes, returning the new quality
+    fn run_until_change(governor: &mut Governor, cost: Duration, max_frames: u32) -> Option<Quality> {
+        (0..max_frames).find_map(|_| governor.record(cost, Duration::ZERO))
+    }
+    
+    #[test]
+    fn test_fast_frames_keep_full_quality() {
+        let mut governor = Governor::new(30);
+        for _ in 0..500 {
+            assert_eq!(governor.record(Duration::from_millis(2), Duration::from_millis(1)), None);
+        }
+        assert_eq!(governor.quality(), Quality::FULL);
+    }
+    
+    #[test]
+    fn test_degrades_effects_before_detail_and_frame_rate() {
+        let mut governor = Governor::new(30);
+        let slow = Duration::from_millis(200);
+        
+        let mut levels = Vec::new();
+        while let Some(quality) = run_until_change(&mut governor, slow, 100) {
+            levels.push(quality);
+        }
+        assert_eq!(levels.len(), governor.max_level());
+        
+        assert!(!levels[0].bloom);
+        assert_eq!(levels[0].color_depth, ColorDepth::TrueColor);
+        
+        // Each level gives something up and never takes anything back
+        for pair in levels.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert!(b.color_depth <= a.color_depth && b.detail <= a.detail && b.fps_scale <= a.fps_scale);
+            assert_ne!(a, b);
+        }
+        
+        // The frame rate is the last thing to go
+        let first_slower = levels.iter().position(|q| q.fps_scale < 1.0).unwrap();
+        assert_eq!(levels[first_slower - 1].detail, Detail::Minimal);
+        assert_eq!(levels[first_slower - 1].color_depth, ColorDepth::Ansi16);
+    }
+    
+    #[test]
+    fn test_single_slow_frame_is_ignored() {
+        let mut governor = Governor::new(30);
+        for i in 0..300 {
+            let cost = if i % 50 == 0 { 100 } else { 5 };
+            assert_eq!(governor.record(Duration::from_millis(cost), Duration::ZERO), None);
+        }
+        assert_eq!(governor.level(), 0);
+    }
+    
+    #[test]
+    fn test_recovers_when_headroom_returns() {
+        let mut governor = Governor::new(30);
+        
+        // About 30ms per frame misses the 26.7ms budget at 30 fps, but not once bloom is off
+        let bloom_cost = Duration::from_millis(30);
+        assert!(run_until_change(&mut governor, bloom_cost, 100).is_some());
+        assert_eq!(governor.level(), 1);
+        assert_eq!(run_until_change(&mut governor, Duration::from_millis(20), 500), None);
+        
+        // Cheap frames bring full quality back, but only after a while
+        assert_eq!(governor.record(Duration::from_millis(2), Duration::ZERO), None);
+        assert_eq!(run_until_change(&mut governor, Duration::from_millis(2), 500), Some(Quality::FULL));
+    }
+    
+    #[test]
+    fn test_budget_follows_target_fps() {
+        let mut governor = Governor::new(30);
+        let budget = governor.budget(0);
+        assert!(budget < Duration::from_secs_f64(1.0 / 30.0));
+        
+        governor.set_target_fps(60);
+        assert!((governor.budget(0).as_secs_f64() * 2.0 - budget.as_secs_f64()).abs() < 1e-6);
+        assert!(governor.budget(governor.max_level()) > governor.budget(0));
+    }
+}
@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+    use glowing_waffle::input::{Action, BindingSpec, KeyBinding, KeyMap};
+    
+    fn key(spec: &str) -> KeyBinding {
+        spec.parse().unwrap()
+    }
+    
+    #[test]
+    fn test_parse_key_bindings() {
+        assert_eq!(key("q"), KeyBinding::char('q'));
+        assert_eq!(key("?"), KeyBinding::char('?'));
+        assert_eq!(key("space"), KeyBinding::char(' '));
+        assert_eq!(key("ctrl+c"), KeyBinding::ctrl('c'));
+        assert_eq!(key("Ctrl+Alt+x"), KeyBinding::new(KeyCode::Char('x'), KeyModifiers::CONTROL | KeyModifiers::ALT));
+        assert_eq!(key("+"), KeyBinding::char('+'));
+        assert_eq!(key("ctrl++"), KeyBinding::ctrl('+'));
+        assert_eq!(key("shift+up"), KeyBinding::new(KeyCode::Up, KeyModifiers::SHIFT));
+        assert_eq!(key("f5"), KeyBinding::new(KeyCode::F(5), KeyModifiers::NONE));
+        
+        // Shift is part of the character: letters become capitals, other characters should be bound as typed
+        assert_eq!(key("shift+a"), KeyBinding::char('A'));
+        assert_eq!(key("ctrl+shift+x"), KeyBinding::ctrl('X'));
+        assert_ne!(key("shift+a"), key("a"));
+        assert!("shift+?".parse::<KeyBinding>().is_err());
+        assert!("shift+1".parse::<KeyBinding>().is_err());
+        
+        assert!("".parse::<KeyBinding>().is_err());
+        assert!("hyper+q".parse::<KeyBinding>().is_err());
+        assert!("f13".parse::<KeyBinding>().is_err());
+        assert!("nope".parse::<KeyBinding>().is_err());
+    }
+    
+    #[test]
+    fn test_key_binding_display_round_trips() {
+        for spec in ["q", "?", "space", "ctrl+c", "alt+left", "ctrl++", "f12", "esc"] {
+            assert_eq!(key(spec).to_string(), spec);
+            assert_eq!(key(&key(spec).to_string()), key(spec));
+        }
+    }
+    
+    #[test]
+    fn test_events_match_bindings() {
+        let keymap = KeyMap::default();
+        
+        // Some terminals report SHIFT along with shifted characters
+        let question = KeyEvent::new(KeyCode::Char('?'), KeyModifiers::SHIFT);
+        assert_eq!(keymap.action_for_event(&question), Some(Action::ToggleHelp));
+        
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action_for_event(&ctrl_c), Some(Action::Quit));
+        let c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE);
+        assert_eq!(keymap.action_for_event(&c), Some(Action::NextColor));
+        
+        let mut release = KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE);
+        release.kind = KeyEventKind::Release;
+        assert_eq!(keymap.action_for_event(&release), None);
+    }
+    
+    #[test]
+    fn test_rebinding() {
+        let mut keymap = KeyMap::default();
+        
+        keymap.apply("x=pause".parse().unwrap());
+        keymap.apply("space=next-size".parse().unwrap());
+        keymap.apply("q=none".parse().unwrap());
+        keymap.apply("==fps-up".parse().unwrap());
+        
+        assert_eq!(keymap.action(KeyBinding::char('x')), Some(Action::TogglePause));
+        assert_eq!(keymap.action(KeyBinding::char(' ')), Some(Action::NextSize));
+        assert_eq!(keymap.action(KeyBinding::char('q')), None);
+        assert_eq!(keymap.action(KeyBinding::char('=')), Some(Action::FpsUp));
+        
+        assert!("x".parse::<BindingSpec>().is_err());
+        assert!("x=dance".parse::<BindingSpec>().is_err());
+    }
+    
+    #[test]
+    fn test_help_lists_bound_actions() {
+        let mut keymap = KeyMap::empty();
+        keymap.bind(KeyBinding::char(' '), Action::TogglePause);
+        keymap.bind(KeyBinding::char('p'), Action::TogglePause);
+        keymap.bind(KeyBinding::ctrl('c'), Action::Quit);
+        
+        let help = keymap.help_lines();
+        assert_eq!(help.len(), 2, "Only bound actions are listed");
+        assert!(help[0].starts_with("space p") && help[0].ends_with(Action::TogglePause.description()));
+        assert!(help[1].starts_with("ctrl+c ") && help[1].ends_with(Action::Quit.description()));
+        
+        // Every action has a name that parses back
+        for action in Action::ALL {
+            assert_eq!(action.name().parse::<Action>().unwrap(), action);
+        }
+    }
+}
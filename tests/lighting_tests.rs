@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use glowing_waffle::{
+        animation::FrameContext,
+        bloom::Bloom,
+        framebuffer::FrameBuffer,
+        lighting::{Light, Lighting},
+        renderer::{ColorMode, Renderer},
+        waffle::{Point3D, Size, Waffle},
+    };
+    use std::collections::HashSet;
+    
+    const EPSILON: f64 = 1e-9;
+    
+    fn origin() -> Point3D {
+        Point3D::new(0.0, 0.0, 0.0)
+    }
+    
+    fn eye() -> Point3D {
+        Point3D::new(0.0, 0.0, 5.0)
+    }
+    
+    #[test]
+    fn test_lambert_diffuse() {
+        let mut lighting = Lighting::new(0.0);
+        lighting.specular = 0.0;
+        lighting.add_light(Light::directional(Point3D::new(0.0, 0.0, -1.0), 1.0));
+        
+        let facing = lighting.luminance(origin(), Point3D::new(0.0, 0.0, 1.0), eye());
+        let tilted = lighting.luminance(origin(), Point3D::new(0.0, 1.0, 1.0), eye());
+        let away = lighting.luminance(origin(), Point3D::new(0.0, 0.0, -1.0), eye());
+        
+        assert!((facing - 1.0).abs() < EPSILON);
+        assert!((tilted - std::f64::consts::FRAC_1_SQRT_2).abs() < EPSILON);
+        assert_eq!(away, 0.0);
+    }
+    
+    #[test]
+    fn test_ambient_and_specular() {
+        let mut lighting = Lighting::new(0.2);
+        assert!((lighting.luminance(origin(), Point3D::new(1.0, 0.0, 0.0), eye()) - 0.2).abs() < EPSILON);
+        
+        // A highlight appears where the light reflects towards the viewer
+        lighting.add_light(Light::directional(Point3D::new(0.0, 0.0, -1.0), 0.3));
+        let mirror = lighting.luminance(origin(), Point3D::new(0.0, 0.0, 1.0), eye());
+        assert!((mirror - (0.2 + 0.3 * (1.0 + lighting.specular))).abs() < EPSILON);
+        
+        // Luminance never exceeds 1
+        lighting.add_light(Light::directional(Point3D::new(0.0, 0.0, -1.0), 5.0));
+        assert_eq!(lighting.luminance(origin(), Point3D::new(0.0, 0.0, 1.0), eye()), 1.0);
+    }
+    
+    #[test]
+    fn test_point_light_falls_off() {
+        let mut lighting = Lighting::new(0.0);
+        lighting.specular = 0.0;
+        lighting.add_light(Light::point(Point3D::new(0.0, 0.0, 2.0), 1.0));
+        
+        let normal = Point3D::new(0.0, 0.0, 1.0);
+        let near = lighting.luminance(Point3D::new(0.0, 0.0, 1.0), normal, eye());
+        let far = lighting.luminance(Point3D::new(0.0, 0.0, -3.0), normal, eye());
+        let behind = lighting.luminance(Point3D::new(0.0, 0.0, 3.0), normal, eye());
+        
+        assert!(near > far && far > 0.0);
+        assert_eq!(behind, 0.0);
+    }
+    
+    #[test]
+    fn test_parse_light_specs() {
+        assert_eq!(
+            "dir:0,0,-2".parse::<Light>().unwrap(),
+            Light::Directional {
+                direction: Point3D::new(0.0, 0.0, -1.0),
+                intensity: 1.0
+            }
+        );
+        assert_eq!(
+            "point: 1, 2, 3 :0.5".parse::<Light>().unwrap(),
+            Light::Point {
+                position: Point3D::new(1.0, 2.0, 3.0),
+                intensity: 0.5
+            }
+        );
+        
+        for bad in ["", "dir", "dir:1,2", "dir:0,0,0", "spot:1,1,1", "dir:1,1,1:x", "point:1,1,1:1:1"] {
+            assert!(bad.parse::<Light>().is_err(), "'{}' should be rejected", bad);
+        }
+    }
+    
+    #[test]
+    fn test_renderer_shades_with_lights() {
+        let mut waffle = Waffle::new(Size::Medium);
+        waffle.set_rotation(0.6, 0.3, 0.1);
+        let mut renderer = Renderer::new(ColorMode::Normal);
+        renderer.set_bloom(Bloom::disabled());
+        
+        let mut chars = |lighting: Lighting| {
+            renderer.set_lighting(lighting);
+            let mut frame = FrameBuffer::new(160, 60);
+            renderer.render_frame(&mut frame, &waffle, &FrameContext::default());
+            frame.cells().iter().map(|cell| cell.ch).filter(|&ch| ch != ' ' && ch != '#').collect::<HashSet<_>>()
+        };
+        
+        // Flat ambient light gives every face the same character
+        assert_eq!(chars(Lighting::new(0.5)).len(), 1);
+        
+        // A directional light picks out faces at different angles
+        assert!(chars(Lighting::default()).len() > 2);
+    }
+}
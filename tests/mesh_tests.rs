@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod tests {
+    use glowing_waffle::{
+        mesh::{Face, Mesh, WaffleShape},
+        waffle::{Size, Waffle},
+    };
+    
+    const EPSILON: f64 = 1e-9;
+    
+    fn shape(pockets: usize) -> WaffleShape {
+        WaffleShape {
+            width: 8.0,
+            height: 6.0,
+            thickness: 1.5,
+            pockets,
+            ridge: 0.5,
+            pocket_depth: 0.75,
+        }
+    }
+    
+    /// Volume enclosed by the mesh, positive when every face points outwards
+    fn signed_volume(mesh: &Mesh) -> f64 {
+        let v = mesh.vertices();
+        mesh.faces()
+            .iter()
+            .flat_map(Face::triangles)
+            .map(|[a, b, c]| v[a].dot(v[b].cross(v[c])) / 6.0)
+            .sum()
+    }
+    
+    #[test]
+    fn test_waffle_face_counts() {
+        for pockets in 1..6 {
+            let mesh = Mesh::waffle(&shape(pockets));
+            let cells = (2 * pockets + 1).pow(2);
+            let pocket_cells = pockets * pockets;
+            
+            // Bottom, 4 sides, the ridges and a floor plus 4 walls per pocket
+            assert_eq!(mesh.faces().len(), 5 + (cells - pocket_cells) + pocket_cells * 5);
+            assert_eq!(mesh.normals().len(), mesh.faces().len());
+            assert_eq!(mesh.triangle_count(), mesh.faces().len() * 2);
+            
+            // 12 slab edges plus rim, floor and corners of every pocket
+            assert_eq!(mesh.edges().len(), 12 + pocket_cells * 12);
+        }
+    }
+    
+    #[test]
+    fn test_waffle_is_a_closed_outward_facing_solid() {
+        let shape = shape(4);
+        let mesh = Mesh::waffle(&shape);
+        
+        let xs_pocket = (shape.width - shape.ridge * 5.0) / 4.0;
+        let ys_pocket = (shape.height - shape.ridge * 5.0) / 4.0;
+        let expected = shape.width * shape.height * shape.thickness - 16.0 * xs_pocket * ys_pocket * shape.pocket_depth;
+        
+        assert!((signed_volume(&mesh) - expected).abs() < 1e-6, "Mesh should enclose the slab minus its pockets");
+    }
+    
+    #[test]
+    fn test_normals_are_unit_and_match_winding() {
+        let mesh = Mesh::waffle(&shape(3));
+        let v = mesh.vertices();
+        
+        for (face, normal) in mesh.faces().iter().zip(mesh.normals()) {
+            assert!((normal.length() - 1.0).abs() < EPSILON);
+            
+            for [a, b, c] in face.triangles() {
+                let winding = (v[b] - v[a]).cross(v[c] - v[a]);
+                assert!(winding.dot(*normal) > 0.0, "Triangle winding should agree with the face normal");
+            }
+        }
+    }
+    
+    #[test]
+    fn test_vertices_are_welded() {
+        let mesh = Mesh::waffle(&shape(2));
+        let v = mesh.vertices();
+        
+        for i in 0..v.len() {
+            for j in i + 1..v.len() {
+                assert!((v[i] - v[j]).length() > 1e-6, "Duplicate vertex at {:?}", v[i]);
+            }
+        }
+        
+        let half_diagonal = (4.0f64.powi(2) + 3.0f64.powi(2) + 0.75f64.powi(2)).sqrt();
+        assert!((mesh.bounding_radius() - half_diagonal).abs() < EPSILON);
+    }
+    
+    #[test]
+    fn test_waffle_uses_mesh() {
+        let mut waffle = Waffle::new(Size::Medium);
+        assert_eq!(waffle.cells().len(), waffle.mesh().vertices().len());
+        assert_eq!(waffle.normals().len(), waffle.mesh().faces().len());
+        
+        // Normals follow the rotation: the top faces +z at rest and -z when flipped over
+        let top = waffle.mesh().normals().iter().position(|n| n.z > 0.99).unwrap();
+        waffle.set_rotation(std::f64::consts::PI, 0.0, 0.0);
+        assert!(waffle.normals()[top].z < -0.99);
+    }
+}
@@ -0,0 +1,192 @@
+#[cfg(test)]
+mod tests {
+    use glowing_waffle::{
+        animation::FrameContext,
+        bloom::Bloom,
+        camera::Camera,
+        framebuffer::FrameBuffer,
+        raster::{self, Fragment, FragmentBuffer, Primitive, ScreenPoint},
+        renderer::{ColorMode, RenderStyle, Renderer},
+        waffle::{Point3D, Size, Waffle},
+    };
+    use std::collections::HashSet;
+    
+    fn covered(a: ScreenPoint, b: ScreenPoint, c: ScreenPoint) -> HashSet<(u16, u16)> {
+        let mut cells = HashSet::new();
+        raster::fill_triangle(40, 40, a, b, c, |x, y, _, _| {
+            cells.insert((x, y));
+        });
+        cells
+    }
+    
+    fn fragment(depth: f64, primitive: Primitive) -> Fragment {
+        Fragment {
+            depth,
+            position: Point3D::new(0.0, 0.0, 0.0),
+            primitive,
+        }
+    }
+    
+    #[test]
+    fn test_triangle_coverage_matches_area() {
+        let cells = covered(
+            ScreenPoint::new(0.0, 0.0, 0.0),
+            ScreenPoint::new(20.0, 0.0, 0.0),
+            ScreenPoint::new(0.0, 20.0, 0.0),
+        );
+        
+        // Half of a 20x20 square, give or take the diagonal
+        assert!((190..=210).contains(&cells.len()), "Covered {} cells", cells.len());
+    }
+    
+    #[test]
+    fn test_both_windings_are_filled() {
+        let a = ScreenPoint::new(2.0, 3.0, 0.0);
+        let b = ScreenPoint::new(30.0, 8.0, 0.0);
+        let c = ScreenPoint::new(12.0, 25.0, 0.0);
+        
+        assert_eq!(covered(a, b, c), covered(a, c, b));
+    }
+    
+    #[test]
+    fn test_adjacent_triangles_leave_no_gaps() {
+        // A quad split along its diagonal covers every cell of the rectangle
+        let p = [
+            ScreenPoint::new(3.0, 4.0, 0.0),
+            ScreenPoint::new(27.0, 4.0, 0.0),
+            ScreenPoint::new(27.0, 19.0, 0.0),
+            ScreenPoint::new(3.0, 19.0, 0.0),
+        ];
+        let mut cells = covered(p[0], p[1], p[2]);
+        cells.extend(covered(p[0], p[2], p[3]));
+        
+        for y in 4..19 {
+            for x in 3..27 {
+                assert!(cells.contains(&(x, y)), "Gap at ({}, {})", x, y);
+            }
+        }
+        assert_eq!(cells.len(), 24 * 15);
+    }
+    
+    #[test]
+    fn test_depth_is_interpolated() {
+        let mut depths = Vec::new();
+        raster::fill_triangle(
+            40,
+            40,
+            ScreenPoint::new(0.0, 0.0, 1.0),
+            ScreenPoint::new(40.0, 0.0, 1.0),
+            ScreenPoint::new(0.0, 40.0, 1.0),
+            |_, _, depth, weights| {
+                depths.push(depth);
+                assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            },
+        );
+        
+        assert!(depths.iter().all(|&depth| (depth - 1.0).abs() < 1e-9));
+    }
+    
+    #[test]
+    fn test_weights_are_perspective_correct() {
+        // Reconstructing the 3D point from the weights lands it on the cell center
+        let camera = Camera::new(Point3D::new(2.0, -3.0, 6.0), Point3D::new(0.0, 0.0, 0.0));
+        let corners = [Point3D::new(-4.0, -1.0, 3.0), Point3D::new(5.0, -2.0, -6.0), Point3D::new(0.0, 4.0, -1.0)];
+        let [a, b, c] = corners.map(|corner| camera.project(corner, 40.0, 40.0).unwrap());
+        
+        let mut visited = 0;
+        raster::fill_triangle(40, 40, a, b, c, |x, y, depth, [wa, wb, wc]| {
+            let point = corners[0] * wa + corners[1] * wb + corners[2] * wc;
+            let p = camera.project(point, 40.0, 40.0).unwrap();
+            assert!((p.x - (x as f64 + 0.5)).abs() < 1e-6 && (p.y - (y as f64 + 0.5)).abs() < 1e-6);
+            assert!((p.depth - depth).abs() < 1e-6);
+            visited += 1;
+        });
+        assert!(visited > 50, "Only {} cells covered", visited);
+        
+        // Halfway across the screen is a quarter of the way along a line that recedes to three times the distance
+        let near = ScreenPoint::with_inv_w(0.0, 0.0, 0.0, 1.0);
+        let far = ScreenPoint::with_inv_w(10.0, 0.0, 0.0, 1.0 / 3.0);
+        raster::draw_line(40, 40, near, far, |x, _, _, t| {
+            if x == 5 {
+                assert!((t - 0.25).abs() < 1e-9, "t = {}", t);
+            }
+        });
+    }
+    
+    #[test]
+    fn test_line_is_continuous() {
+        let mut cells = Vec::new();
+        raster::draw_line(
+            40,
+            40,
+            ScreenPoint::new(1.0, 2.0, 0.0),
+            ScreenPoint::new(30.0, 13.0, 1.0),
+            |x, y, depth, t| cells.push((x, y, depth, t)),
+        );
+        
+        assert_eq!(cells.first().map(|c| (c.0, c.1)), Some((1, 2)));
+        assert_eq!(cells.last().map(|c| (c.0, c.1)), Some((30, 13)));
+        assert_eq!(cells.len(), 30, "One cell per step along the major axis");
+        assert!((cells.last().unwrap().2 - 1.0).abs() < 1e-9);
+        
+        for pair in cells.windows(2) {
+            let dx = (pair[1].0 as i32 - pair[0].0 as i32).abs();
+            let dy = (pair[1].1 as i32 - pair[0].1 as i32).abs();
+            assert!(dx <= 1 && dy <= 1 && dx + dy > 0, "Line should step to a neighbouring cell");
+        }
+    }
+    
+    #[test]
+    fn test_depth_buffer_keeps_closest_regardless_of_order() {
+        let mut buffer = FragmentBuffer::new(4, 4);
+        
+        assert!(buffer.test_and_set(1, 1, fragment(0.2, Primitive::Face(0))));
+        assert!(buffer.test_and_set(1, 1, fragment(0.8, Primitive::Face(1))));
+        assert!(!buffer.test_and_set(1, 1, fragment(0.5, Primitive::Face(2))));
+        assert_eq!(buffer.get(1, 1).map(|f| f.primitive), Some(Primitive::Face(1)));
+        assert_eq!(buffer.coverage(), 1);
+        
+        buffer.reset(4, 4);
+        assert_eq!(buffer.coverage(), 0);
+    }
+    
+    #[test]
+    fn test_solid_waffle_has_no_holes() {
+        // Seen straight on, the waffle is a filled rectangle
+        let waffle = Waffle::new(Size::Small);
+        let mut renderer = Renderer::new(ColorMode::Normal);
+        renderer.set_bloom(Bloom::disabled());
+        let mut frame = FrameBuffer::new(120, 60);
+        renderer.render_frame(&mut frame, &waffle, &FrameContext::default());
+        
+        let filled: Vec<_> = frame
+            .rows()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().filter(|(_, c)| !c.is_empty()).map(move |(x, _)| (x, y)))
+            .collect();
+        let min_x = filled.iter().map(|p| p.0).min().unwrap();
+        let max_x = filled.iter().map(|p| p.0).max().unwrap();
+        let min_y = filled.iter().map(|p| p.1).min().unwrap();
+        let max_y = filled.iter().map(|p| p.1).max().unwrap();
+        
+        assert_eq!(filled.len(), (max_x - min_x + 1) * (max_y - min_y + 1), "Solid surface should have no gaps");
+    }
+    
+    #[test]
+    fn test_styles_cover_increasing_area() {
+        let mut waffle = Waffle::new(Size::Medium);
+        waffle.set_rotation(0.6, 0.3, 0.1);
+        let mut renderer = Renderer::new(ColorMode::Normal);
+        renderer.set_bloom(Bloom::disabled());
+        
+        let mut coverage = Vec::new();
+        for style in [RenderStyle::Points, RenderStyle::Wireframe, RenderStyle::Solid] {
+            renderer.set_style(style);
+            let mut frame = FrameBuffer::new(200, 80);
+            renderer.render_frame(&mut frame, &waffle, &FrameContext::default());
+            coverage.push(frame.cells().iter().filter(|c| !c.is_empty()).count());
+        }
+        
+        assert!(coverage[0] < coverage[1] && coverage[1] < coverage[2], "Coverage: {:?}", coverage);
+    }
+}
@@ -0,0 +1,195 @@
+#[cfg(test)]
+mod tests {
+    use glowing_waffle::{
+        animation::FrameContext,
+        bloom::Bloom,
+        color::ColorDepth,
+        camera::Camera,
+        framebuffer::{FrameBuffer, HeadlessTarget},
+        renderer::{ColorMode, RenderStyle, Renderer, Stage},
+        waffle::{Point3D, Size, Waffle},
+    };
+    
+    fn render_at(mode: ColorMode, time: f64) -> FrameBuffer {
+        let mut renderer = Renderer::new(mode);
+        renderer.set_bloom(Bloom::disabled());
+        let mut waffle = Waffle::new(Size::Medium);
+        waffle.set_rotation(0.6, 0.3, 0.1);
+        
+        let mut frame = FrameBuffer::new(80, 24);
+        renderer.render_frame(&mut frame, &waffle, &FrameContext::new(time, 0, 0.0));
+        frame
+    }
+    
+    #[test]
+    fn test_rainbow_cycles_with_time() {
+        let start = render_at(ColorMode::Rainbow, 0.0);
+        let later = render_at(ColorMode::Rainbow, 1.5);
+        
+        assert_eq!(start.to_text(), later.to_text(), "Time should only affect colors");
+        assert_ne!(start, later, "Rainbow colors should shift over time");
+        
+        // A full hue cycle brings the colors back
+        let cycled = render_at(ColorMode::Rainbow, 2.0 * std::f64::consts::PI);
+        let matching = start
+            .cells()
+            .iter()
+            .zip(cycled.cells())
+            .filter(|(a, b)| a.color == b.color)
+            .count();
+        assert!(matching * 10 >= start.cells().len() * 9, "Hue should wrap around after a full cycle");
+    }
+    
+    #[test]
+    fn test_fire_flickers_with_time() {
+        assert_ne!(render_at(ColorMode::Fire, 0.0), render_at(ColorMode::Fire, 0.4));
+    }
+    
+    #[test]
+    fn test_normal_is_static() {
+        assert_eq!(render_at(ColorMode::Normal, 0.0), render_at(ColorMode::Normal, 3.0));
+    }
+    
+    /// Columns and rows spanned by the drawn waffle
+    fn extent(frame: &FrameBuffer) -> (usize, usize) {
+        let rows: Vec<usize> = frame
+            .rows()
+            .enumerate()
+            .filter(|(_, row)| row.iter().any(|cell| !cell.is_empty()))
+            .map(|(y, _)| y)
+            .collect();
+        let columns: Vec<usize> = (0..frame.width() as usize)
+            .filter(|&x| frame.rows().any(|row| !row[x].is_empty()))
+            .collect();
+        (
+            columns.last().unwrap() - columns[0] + 1,
+            rows.last().unwrap() - rows[0] + 1,
+        )
+    }
+    
+    fn render_to(target: &mut HeadlessTarget, fallback: f64) -> (usize, usize) {
+        let mut renderer = Renderer::new(ColorMode::Normal);
+        renderer.set_bloom(Bloom::disabled());
+        renderer.set_cell_aspect(fallback);
+        let waffle = Waffle::new(Size::Medium);
+        
+        renderer.render(target, &waffle, &FrameContext::default()).unwrap();
+        extent(target.frame())
+    }
+    
+    #[test]
+    fn test_cell_aspect_keeps_waffle_square() {
+        // A square waffle seen head-on spans `aspect` times as many columns as rows
+        for aspect in [1.0, 2.0, 2.5] {
+            let mut target = HeadlessTarget::new(120, 40);
+            target.set_cell_aspect(Some(aspect));
+            let (columns, rows) = render_to(&mut target, 2.0);
+            let ratio = columns as f64 / rows as f64;
+            assert!((ratio - aspect).abs() < 0.25, "Expected ratio {} but got {}", aspect, ratio);
+        }
+    }
+    
+    #[test]
+    fn test_cell_aspect_fallback() {
+        // Targets that can't measure their cells use the configured ratio
+        let mut measured = HeadlessTarget::new(120, 40);
+        measured.set_cell_aspect(Some(1.5));
+        let mut unmeasured = HeadlessTarget::new(120, 40);
+        
+        assert_eq!(render_to(&mut measured, 2.0), render_to(&mut unmeasured, 1.5));
+    }
+    
+    #[test]
+    fn test_auto_fit_on_resize() {
+        let mut renderer = Renderer::new(ColorMode::Normal);
+        renderer.set_bloom(Bloom::disabled());
+        let mut waffle = Waffle::new(Size::Large);
+        waffle.set_rotation(0.4, 0.2, 0.7);
+        
+        for (width, height) in [(40, 12), (80, 24), (240, 70)] {
+            let mut target = HeadlessTarget::new(width, height);
+            renderer.render(&mut target, &waffle, &FrameContext::default()).unwrap();
+            let frame = target.frame();
+            
+            // Nothing touches the border, yet the waffle uses a good part of the screen
+            let top = frame.rows().next().unwrap();
+            let bottom = frame.rows().last().unwrap();
+            assert!(top.iter().chain(bottom).all(|cell| cell.is_empty()), "Clipped at {}x{}", width, height);
+            assert!(frame.rows().all(|row| row[0].is_empty() && row[width as usize - 1].is_empty()));
+            
+            let (_, rows) = extent(frame);
+            assert!(rows * 2 >= height as usize, "Only {} of {} rows used", rows, height);
+        }
+    }
+    
+    #[test]
+    fn test_color_depth_limits_palette() {
+        use crossterm::style::Color;
+        
+        let truecolor = render_at(ColorMode::Rainbow, 1.0);
+        assert!(truecolor.cells().iter().any(|cell| matches!(cell.color, Color::Rgb { .. })));
+        
+        for depth in [ColorDepth::Ansi256, ColorDepth::Ansi16] {
+            let mut renderer = Renderer::new(ColorMode::Rainbow);
+            renderer.set_bloom(Bloom::disabled());
+            renderer.set_color_depth(depth);
+            let mut waffle = Waffle::new(Size::Medium);
+            waffle.set_rotation(0.6, 0.3, 0.1);
+            
+            let mut frame = FrameBuffer::new(80, 24);
+            renderer.render_frame(&mut frame, &waffle, &FrameContext::new(1.0, 0, 0.0));
+            
+            // Same picture, fewer colors
+            assert_eq!(frame.to_text(), truecolor.to_text());
+            for cell in frame.cells() {
+                match cell.color {
+                    Color::Rgb { .. } => panic!("{:?} left a 24-bit color", depth),
+                    Color::AnsiValue(_) => assert_eq!(depth, ColorDepth::Ansi256),
+                    _ => {},
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn test_stage_timings() {
+        let mut renderer = Renderer::new(ColorMode::Normal);
+        let mut waffle = Waffle::new(Size::Medium);
+        waffle.set_rotation(0.6, 0.3, 0.1);
+        let mut target = HeadlessTarget::new(80, 24);
+        renderer.render(&mut target, &waffle, &FrameContext::new(1.0, 0, 0.0)).unwrap();
+        
+        let timing = renderer.timing();
+        assert_eq!(timing.transform, waffle.transform_time());
+        assert!(timing.rasterize > std::time::Duration::ZERO);
+        assert!(timing.shade > std::time::Duration::ZERO);
+        assert_eq!(timing.render(), timing.project + timing.rasterize + timing.shade);
+        assert_eq!(timing.total(), Stage::ALL.iter().map(|&stage| timing.stage(stage)).sum());
+        assert!(Stage::ALL.iter().enumerate().all(|(index, stage)| stage.index() == index));
+    }
+    
+    #[test]
+    fn test_faces_crossing_the_near_plane_are_clipped() {
+        // Just under the waffle and looking up across it, its underside reaches behind
+        // the camera yet still covers the top of the screen
+        let mut renderer = Renderer::new(ColorMode::Normal);
+        renderer.set_bloom(Bloom::disabled());
+        renderer.set_auto_fit(false);
+        renderer.set_camera(Camera {
+            up: Point3D::new(0.0, 0.0, 1.0),
+            ..Camera::new(Point3D::new(0.0, -1.0, -1.5), Point3D::new(0.0, 2.5, -0.5))
+        });
+        let waffle = Waffle::new(Size::Small);
+        
+        let mut frame = FrameBuffer::new(80, 24);
+        renderer.render_frame(&mut frame, &waffle, &FrameContext::default());
+        let top = frame.rows().next().unwrap();
+        assert!(top.iter().all(|cell| !cell.is_empty()), "{}", frame.to_text());
+        
+        // Edges running over the camera are cut short rather than dropped
+        renderer.set_style(RenderStyle::Wireframe);
+        renderer.render_frame(&mut frame, &waffle, &FrameContext::default());
+        let top = frame.rows().next().unwrap();
+        assert!(top.iter().any(|cell| !cell.is_empty()), "{}", frame.to_text());
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    
+    use glowing_waffle::{rotation::Rotation, waffle::Point3D};
+    
+    const EPSILON: f64 = 1e-9;
+    
+    fn close(a: Point3D, b: Point3D) -> bool {
+        (a - b).length() < EPSILON
+    }
+    
+    #[test]
+    fn test_euler_angles_match_point_rotation() {
+        let point = Point3D::new(0.4, -1.3, 2.2);
+        let (x, y, z) = (0.7, 1.9, -0.4);
+        
+        let mut expected = point;
+        expected.rotate_x(x);
+        expected.rotate_y(y);
+        expected.rotate_z(z);
+        assert!(close(Rotation::from_euler(x, y, z).apply(point), expected));
+    }
+    
+    #[test]
+    fn test_axis_angle() {
+        let quarter = Rotation::from_axis_angle(Point3D::new(0.0, 0.0, 2.0), PI / 2.0);
+        assert!(close(quarter.apply(Point3D::new(1.0, 0.0, 0.0)), Point3D::new(0.0, 1.0, 0.0)));
+        assert!((quarter.angle() - PI / 2.0).abs() < EPSILON);
+        assert!(close(quarter.axis(), Point3D::new(0.0, 0.0, 1.0)));
+        
+        let vector = Rotation::from_vector(Point3D::new(0.0, 0.0, PI / 2.0));
+        assert!(close(vector.apply(Point3D::new(1.0, 0.0, 0.0)), Point3D::new(0.0, 1.0, 0.0)));
+        
+        assert_eq!(Rotation::from_vector(Point3D::default()), Rotation::IDENTITY);
+        assert_eq!(Rotation::IDENTITY.angle(), 0.0);
+    }
+    
+    #[test]
+    fn test_then_applies_in_order() {
+        let x = Rotation::from_axis_angle(Point3D::new(1.0, 0.0, 0.0), PI / 2.0);
+        let z = Rotation::from_axis_angle(Point3D::new(0.0, 0.0, 1.0), PI / 2.0);
+        let point = Point3D::new(0.0, 1.0, 0.0);
+        
+        // Up turns towards the viewer, then stays there
+        assert!(close(x.then(z).apply(point), Point3D::new(0.0, 0.0, 1.0)));
+        // Up turns to the left, then stays there
+        assert!(close(z.then(x).apply(point), Point3D::new(-1.0, 0.0, 0.0)));
+        
+        // Many small turns stay a proper rotation
+        let step = Rotation::from_axis_angle(Point3D::new(1.0, 2.0, 3.0), 0.001);
+        let turned = (0..10_000).fold(Rotation::IDENTITY, |rotation, _| rotation.then(step));
+        assert!((turned.apply(point).length() - 1.0).abs() < EPSILON);
+    }
+}
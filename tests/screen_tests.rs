@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Color;
+    use glowing_waffle::{
+        framebuffer::{Cell, FrameBuffer},
+        screen::{OutputStats, ScreenBuffer},
+    };
+    
+    const RED: Color = Color::Rgb { r: 255, g: 0, b: 0 };
+    const BLUE: Color = Color::Rgb { r: 0, g: 0, b: 255 };
+    
+    fn encode(screen: &mut ScreenBuffer, frame: &FrameBuffer) -> String {
+        let mut out = Vec::new();
+        screen.write_frame(frame, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+    
+    #[test]
+    fn test_first_frame_is_drawn_in_full() {
+        let mut screen = ScreenBuffer::new();
+        let mut frame = FrameBuffer::new(10, 3);
+        frame.set(2, 1, Cell::new('#', RED, 0.0));
+        
+        let output = encode(&mut screen, &frame);
+        
+        assert!(output.contains("\x1b[2J"), "First frame should clear the screen");
+        assert!(output.contains('#'));
+        assert_eq!(screen.front(), &frame);
+    }
+    
+    #[test]
+    fn test_unchanged_frame_emits_nothing() {
+        let mut screen = ScreenBuffer::new();
+        let mut frame = FrameBuffer::new(10, 3);
+        frame.set(2, 1, Cell::new('#', RED, 0.0));
+        
+        encode(&mut screen, &frame);
+        assert_eq!(encode(&mut screen, &frame), "");
+        
+        // Depth alone does not change what is on screen
+        frame.set(2, 1, Cell::new('#', RED, 5.0));
+        assert_eq!(encode(&mut screen, &frame), "");
+    }
+    
+    #[test]
+    fn test_only_changed_cells_are_emitted() {
+        let mut screen = ScreenBuffer::new();
+        let mut frame = FrameBuffer::new(20, 5);
+        frame.set(0, 0, Cell::new('a', RED, 0.0));
+        encode(&mut screen, &frame);
+        
+        frame.set(10, 3, Cell::new('b', RED, 0.0));
+        let output = encode(&mut screen, &frame);
+        
+        assert!(!output.contains("\x1b[2J"), "Incremental frames should not clear");
+        assert!(!output.contains('a'), "Unchanged cell should not be re-emitted");
+        assert!(output.contains("\x1b[4;11H"), "Cursor should move to the changed cell");
+        assert!(output.ends_with('b'));
+        assert!(!output.contains("38;2"), "Color is already set and should not be repeated");
+    }
+    
+    #[test]
+    fn test_runs_are_coalesced() {
+        let mut screen = ScreenBuffer::new();
+        let mut frame = FrameBuffer::new(20, 2);
+        encode(&mut screen, &frame);
+        
+        for (i, ch) in "abcd".chars().enumerate() {
+            frame.set(3 + i as u16, 0, Cell::new(ch, RED, 0.0));
+        }
+        for (i, ch) in "ef".chars().enumerate() {
+            frame.set(7 + i as u16, 0, Cell::new(ch, BLUE, 0.0));
+        }
+        let output = encode(&mut screen, &frame);
+        
+        assert_eq!(output.matches("\x1b[1;").count(), 1, "A single cursor move should cover the run");
+        assert_eq!(output.matches("38;2").count(), 2, "One color change per color segment");
+        assert!(output.contains("abcd"));
+        assert!(output.contains("ef"));
+    }
+    
+    #[test]
+    fn test_invalidate_and_resize_force_redraw() {
+        let mut screen = ScreenBuffer::new();
+        let mut frame = FrameBuffer::new(10, 3);
+        frame.set(1, 1, Cell::new('x', RED, 0.0));
+        encode(&mut screen, &frame);
+        
+        screen.invalidate();
+        let output = encode(&mut screen, &frame);
+        assert!(output.contains("\x1b[2J"));
+        assert!(output.contains('x'));
+        
+        frame.resize(12, 4);
+        assert!(encode(&mut screen, &frame).contains("\x1b[2J"));
+    }
+    
+    #[test]
+    fn test_output_is_counted() {
+        let mut screen = ScreenBuffer::new();
+        let mut frame = FrameBuffer::new(20, 5);
+        frame.set(0, 0, Cell::new('a', RED, 0.0));
+        let output = encode(&mut screen, &frame);
+        
+        // Clear, move to the first changed cell and set its color
+        let stats = screen.last_output();
+        assert_eq!(stats.bytes, output.len() as u64);
+        assert_eq!((stats.escapes, stats.cells_changed), (3, 1));
+        
+        frame.set(10, 3, Cell::new('b', BLUE, 0.0));
+        frame.set(11, 3, Cell::new('c', BLUE, 0.0));
+        let output = encode(&mut screen, &frame);
+        let stats = screen.last_output();
+        assert_eq!(stats.bytes, output.len() as u64);
+        assert_eq!((stats.escapes, stats.cells_changed), (2, 2));
+        
+        encode(&mut screen, &frame);
+        assert_eq!(screen.last_output(), OutputStats::default());
+    }
+}
@@ -2,6 +2,31 @@
 mod tests {
     use std::time::Duration;
     
+    use glowing_waffle::{color::ColorDepth, terminal::cell_aspect_from_pixels};
+    
+    #[test]
+    fn test_cell_aspect_from_pixels() {
+        // 80x24 cells of 9x18 pixels
+        assert_eq!(cell_aspect_from_pixels(80, 24, 720, 432), Some(2.0));
+        
+        // Terminals that don't know their pixel size report zero
+        assert_eq!(cell_aspect_from_pixels(80, 24, 0, 0), None);
+        assert_eq!(cell_aspect_from_pixels(0, 0, 720, 432), None);
+        
+        // Nonsense sizes fall back to the configured ratio
+        assert_eq!(cell_aspect_from_pixels(80, 24, 80, 2400), None);
+    }
+    
+    #[test]
+    fn test_color_depth_from_environment() {
+        assert_eq!(ColorDepth::detect(Some("truecolor"), Some("xterm-256color")), ColorDepth::TrueColor);
+        assert_eq!(ColorDepth::detect(Some("24bit"), None), ColorDepth::TrueColor);
+        assert_eq!(ColorDepth::detect(None, Some("xterm-direct")), ColorDepth::TrueColor);
+        assert_eq!(ColorDepth::detect(None, Some("screen-256color")), ColorDepth::Ansi256);
+        assert_eq!(ColorDepth::detect(None, Some("vt100")), ColorDepth::Ansi16);
+        assert_eq!(ColorDepth::detect(None, None), ColorDepth::Ansi16);
+    }
+    
     // These tests can only be run in a manual fashion since they interact with the terminal
     // and require user input
     
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    
+    use glowing_waffle::timestep::{FixedTimestep, FrameStats};
+    
+    const STEP: Duration = Duration::from_millis(10);
+    
+    #[test]
+    fn test_accumulates_fixed_steps() {
+        let mut timestep = FixedTimestep::new(STEP);
+        
+        let steps = timestep.accumulate(Duration::from_millis(25));
+        assert_eq!(steps.count, 2);
+        assert!((steps.alpha - 0.5).abs() < 1e-9);
+        
+        // The leftover half step carries over
+        let steps = timestep.accumulate(Duration::from_millis(5));
+        assert_eq!(steps.count, 1);
+        assert!(steps.alpha.abs() < 1e-9);
+        
+        let steps = timestep.accumulate(Duration::from_millis(3));
+        assert_eq!(steps.count, 0);
+        assert!((steps.alpha - 0.3).abs() < 1e-9);
+        assert_eq!(timestep.total_steps(), 3);
+    }
+    
+    #[test]
+    fn test_skips_steps_when_behind() {
+        let mut timestep = FixedTimestep::new(STEP);
+        timestep.set_max_steps(4);
+        
+        let steps = timestep.accumulate(Duration::from_millis(105));
+        assert_eq!((steps.count, steps.skipped), (4, 6));
+        assert!((steps.alpha - 0.5).abs() < 1e-9, "The fraction of a step is kept");
+        assert_eq!(timestep.total_skipped(), 6);
+        
+        // Once caught up, frames run normally again
+        let steps = timestep.accumulate(Duration::from_millis(10));
+        assert_eq!((steps.count, steps.skipped), (1, 0));
+    }
+    
+    #[test]
+    fn test_reset_and_rate() {
+        let mut timestep = FixedTimestep::from_rate(50);
+        assert_eq!(timestep.step(), Duration::from_millis(20));
+        assert!((timestep.rate() - 50.0).abs() < 1e-9);
+        
+        timestep.accumulate(Duration::from_millis(15));
+        timestep.reset();
+        assert_eq!(timestep.accumulate(Duration::from_millis(15)).count, 0);
+    }
+    
+    #[test]
+    fn test_frame_stats() {
+        let mut stats = FrameStats::new();
+        assert_eq!(stats.fps(), 0.0);
+        
+        for _ in 0..100 {
+            stats.record_frame(Duration::from_millis(20));
+        }
+        assert!((stats.fps() - 50.0).abs() < 1e-9);
+        
+        // Only the last second counts
+        for _ in 0..10 {
+            stats.record_frame(Duration::from_millis(100));
+        }
+        assert!((stats.fps() - 10.0).abs() < 1e-9);
+        assert_eq!(stats.worst_interval(), Duration::from_millis(100));
+        
+        // Very slow frames still report a rate
+        stats.record_frame(Duration::from_secs(4));
+        assert!((stats.fps() - 0.25).abs() < 1e-9);
+    }
+}
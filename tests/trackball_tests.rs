@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    
+    use glowing_waffle::{
+        camera::Camera,
+        rotation::Rotation,
+        trackball::Trackball,
+        waffle::{Point3D, Size, Waffle},
+    };
+    
+    const FRAME: Duration = Duration::from_millis(20);
+    
+    const EPSILON: f64 = 1e-9;
+    
+    fn close(a: Point3D, b: Point3D) -> bool {
+        (a - b).length() < EPSILON
+    }
+    
+    #[test]
+    fn test_drag_rotates_waffle() {
+        let mut waffle = Waffle::new(Size::Small);
+        let mut trackball = Trackball::new();
+        let camera = Camera::default();
+        let top = Point3D::new(0.0, 1.0, 0.0);
+        let front = Point3D::new(0.0, 0.0, 1.0);
+        
+        trackball.press(10, 10);
+        assert!(trackball.is_active() && trackball.is_dragging());
+        
+        // Dragging down by half the screen height tips the top towards the viewer
+        trackball.drag(&mut waffle, &camera, 10, 22, 24);
+        assert!(close(waffle.orientation().apply(top), front));
+        assert_eq!(waffle.rotation(), (0.0, 0.0, 0.0), "The animation angles are left alone");
+        
+        // Horizontal drags account for narrow cells: 24 columns span as far as 12 rows
+        waffle.set_view_turn(Rotation::IDENTITY);
+        trackball.drag(&mut waffle, &camera, 34, 22, 24);
+        let right = Point3D::new(1.0, 0.0, 0.0);
+        assert!(close(waffle.orientation().apply(front), right), "Dragging right turns the front to the right");
+    }
+    
+    #[test]
+    fn test_drags_turn_around_screen_axes() {
+        let mut waffle = Waffle::new(Size::Small);
+        waffle.set_rotation(0.3, 0.2, 0.6);
+        let mut trackball = Trackball::new();
+        let camera = Camera::default();
+        
+        // Pitch a quarter turn, then drag sideways
+        trackball.press(40, 0);
+        trackball.drag(&mut waffle, &camera, 40, 12, 24);
+        let before: Vec<Point3D> = waffle.cells().iter().map(|cell| cell.point).collect();
+        trackball.drag(&mut waffle, &camera, 50, 12, 24);
+        
+        // Still a turn around the screen's vertical axis, which keeps every height
+        let mut moved = false;
+        for (cell, old) in waffle.cells().iter().zip(&before) {
+            assert!((cell.point.y - old.y).abs() < EPSILON, "{:?} left its height {:?}", cell.point, old);
+            moved |= (cell.point - *old).length() > 0.1;
+        }
+        assert!(moved);
+    }
+    
+    #[test]
+    fn test_drags_follow_the_camera() {
+        let mut waffle = Waffle::new(Size::Small);
+        let mut trackball = Trackball::new();
+        
+        // Looking at the waffle from its right side, from further away than the default
+        let camera = Camera::new(Point3D::new(30.0, 0.0, 0.0), Point3D::default());
+        
+        // Dragging down still tips the top towards the viewer
+        trackball.press(10, 10);
+        trackball.drag(&mut waffle, &camera, 10, 22, 24);
+        let top = waffle.orientation().apply(Point3D::new(0.0, 1.0, 0.0));
+        assert!(close(top, Point3D::new(1.0, 0.0, 0.0)), "{:?}", top);
+    }
+    
+    #[test]
+    fn test_release_coasts_and_settles() {
+        let mut waffle = Waffle::new(Size::Small);
+        let mut trackball = Trackball::new();
+        let camera = Camera::default();
+        
+        trackball.press(0, 10);
+        for column in 1..=5 {
+            trackball.drag(&mut waffle, &camera, column * 4, 10, 24);
+            trackball.update(&mut waffle, FRAME);
+        }
+        trackball.release();
+        assert!(trackball.is_active() && !trackball.is_dragging());
+        
+        // The spin is around the screen's vertical axis, the way the waffle was dragged
+        let spin = trackball.velocity();
+        assert!(spin.y > 0.0 && spin.x.abs() < EPSILON && spin.z.abs() < EPSILON);
+        
+        // The waffle keeps turning around that axis, ever slower
+        let front = |waffle: &Waffle| waffle.orientation().apply(Point3D::new(0.0, 0.0, 1.0));
+        let mut last = front(&waffle);
+        let mut last_step = f64::INFINITY;
+        for _ in 0..10 {
+            trackball.update(&mut waffle, FRAME);
+            let current = front(&waffle);
+            let step = last.cross(current);
+            assert!(step.y > 0.0 && step.y < last_step);
+            assert!(current.y.abs() < EPSILON);
+            last = current;
+            last_step = step.y;
+        }
+        
+        // Eventually the spin dies down and control is handed back
+        for _ in 0..1000 {
+            trackball.update(&mut waffle, FRAME);
+        }
+        assert!(!trackball.is_active());
+        assert_eq!(trackball.velocity(), Point3D::default());
+    }
+    
+    #[test]
+    fn test_holding_still_drops_the_spin() {
+        let mut waffle = Waffle::new(Size::Small);
+        let mut trackball = Trackball::new();
+        let camera = Camera::default();
+        
+        trackball.press(0, 0);
+        trackball.drag(&mut waffle, &camera, 20, 0, 24);
+        trackball.update(&mut waffle, FRAME);
+        for _ in 0..30 {
+            trackball.update(&mut waffle, FRAME);
+        }
+        trackball.release();
+        
+        assert!(!trackball.is_active(), "Releasing a still pointer shouldn't spin");
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use glowing_waffle::waffle::{Size, Waffle};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    
+    const EPSILON: f64 = 1e-9;
+    
+    fn length(x: f64, y: f64, z: f64) -> f64 {
+        (x * x + y * y + z * z).sqrt()
+    }
+    
+    #[test]
+    fn test_cells_keep_their_identity() {
+        let mut waffle = Waffle::new(Size::Medium);
+        let mut rng = StdRng::seed_from_u64(7);
+        
+        for _ in 0..1000 {
+            waffle.rotate(rng.gen_range(-0.2..0.2), rng.gen_range(-0.2..0.2), rng.gen_range(-0.2..0.2));
+            
+            for (i, (cell, vertex)) in waffle.cells().iter().zip(waffle.vertices()).enumerate() {
+                assert_eq!(cell.id, i);
+                assert_eq!(cell.is_edge, vertex.is_edge, "Edge flag moved to another point");
+                
+                // Rotation never changes a point's distance from the center
+                let p = vertex.position;
+                let q = cell.point;
+                assert!((length(p.x, p.y, p.z) - length(q.x, q.y, q.z)).abs() < EPSILON);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_model_is_invariant_over_rotations() {
+        let mut waffle = Waffle::new(Size::Large);
+        let model: Vec<_> = waffle
+            .vertices()
+            .iter()
+            .map(|v| (v.position.x, v.position.y, v.position.z, v.is_edge))
+            .collect();
+        let mut rng = StdRng::seed_from_u64(42);
+        
+        for _ in 0..500 {
+            waffle.rotate(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+        }
+        
+        // Returning to the rest pose reproduces the original model exactly
+        waffle.set_rotation(0.0, 0.0, 0.0);
+        
+        assert_eq!(waffle.cells().len(), model.len());
+        for (cell, &(x, y, z, is_edge)) in waffle.cells().iter().zip(&model) {
+            assert!((cell.point.x - x).abs() < EPSILON);
+            assert!((cell.point.y - y).abs() < EPSILON);
+            assert!((cell.point.z - z).abs() < EPSILON);
+            assert_eq!(cell.is_edge, is_edge);
+        }
+    }
+    
+    #[test]
+    fn test_same_rotation_same_points() {
+        let mut a = Waffle::new(Size::Small);
+        let mut b = Waffle::new(Size::Small);
+        
+        // Reach the same angles along different paths
+        for _ in 0..300 {
+            a.rotate(0.01, 0.02, 0.003);
+        }
+        let (x, y, z) = a.rotation();
+        b.rotate(0.5, -0.3, 1.1);
+        b.set_rotation(x, y, z);
+        
+        for (pa, pb) in a.cells().iter().zip(b.cells()) {
+            assert!((pa.point.x - pb.point.x).abs() < EPSILON);
+            assert!((pa.point.y - pb.point.y).abs() < EPSILON);
+            assert!((pa.point.z - pb.point.z).abs() < EPSILON);
+        }
+    }
+    
+    #[test]
+    fn test_sorted_cells_are_a_depth_ordered_permutation() {
+        let mut waffle = Waffle::new(Size::Medium);
+        waffle.set_rotation(0.7, 1.3, 0.2);
+        
+        let sorted: Vec<_> = waffle.sorted_cells().collect();
+        assert_eq!(sorted.len(), waffle.cells().len());
+        assert!(sorted.windows(2).all(|pair| pair[0].depth >= pair[1].depth));
+        
+        let mut ids: Vec<_> = sorted.iter().map(|cell| cell.id).collect();
+        ids.sort_unstable();
+        assert!(ids.iter().enumerate().all(|(i, &id)| i == id));
+    }
+}